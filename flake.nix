@@ -35,6 +35,8 @@
               (fs.fileFilter (file: file.hasExt "rs") ./.)
               # *.svg
               (fs.fileFilter (file: file.hasExt "svg") ./assets)
+              # Wayland protocol definitions
+              (fs.fileFilter (file: file.hasExt "xml") ./protocols)
             ];
           in rustPlatform.buildRustPackage {
             pname = "limbo-rs";
@@ -112,6 +112,26 @@ impl<V: Lerpable> Eased<V> {
         self.target_idx = target_idx;
     }
 
+    /// Replace the target values, animating from the current value if the current target moved.
+    pub fn with_targets(mut self, targets: &[V]) -> Self
+    where
+        V: PartialEq,
+    {
+        assert!(
+            self.target_idx < targets.len(),
+            "targets must contain the current target index"
+        );
+
+        let end = targets[self.target_idx];
+        if end != self.end {
+            self.start = self.get();
+            self.end = end;
+            self.progress = 0.0;
+        }
+        self.targets = targets.into();
+        self
+    }
+
     pub fn is_running(&self) -> bool {
         self.progress < 1.0
     }
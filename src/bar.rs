@@ -1,13 +1,13 @@
 use std::rc::Rc;
 
 use iced::platform_specific::shell::commands::layer_surface::{
-    destroy_layer_surface, get_layer_surface,
+    destroy_layer_surface, get_layer_surface, set_margin,
 };
 use iced::runtime::platform_specific::wayland::layer_surface::{
     IcedMargin, IcedOutput, SctkLayerSurfaceSettings,
 };
-use iced::widget::{Row, container, row};
-use iced::{Alignment, Element, Event, Length, Size, Task, Theme, window};
+use iced::widget::{column, container, row};
+use iced::{Alignment, Border, Element, Event, Length, Size, Task, Theme, mouse, window};
 use sctk::reexports::client::protocol::wl_output::WlOutput;
 use sctk::shell::wlr_layer::{Anchor, KeyboardInteractivity, Layer};
 
@@ -15,10 +15,13 @@ use crate::GlobalState;
 use crate::animation::{Eased, Easing};
 use crate::components::{icon, side};
 use crate::config::Config;
-use crate::config::types::ModuleName;
+use crate::config::types::{self, BarLayer, BarPosition, ModuleName, TransparencyPolicy};
 use crate::desktop_environment::WorkspaceInfo;
 use crate::message::Message;
-use crate::sections::{Clock, Sysmon, TrayView, Workspaces};
+use crate::sections::{Clock, KeyboardLayout, Submap, Sysmon, TrayView, WindowTitle, Workspaces};
+
+/// Thickness of the part of an auto-hidden bar left on screen, which reveals it on hover.
+const REVEAL_STRIP: i32 = 2;
 
 pub struct Bar {
     /// window id of the bar's layer surface.
@@ -27,12 +30,20 @@ pub struct Bar {
     output_name: String,
     size: Option<Size>,
     background_alpha_factor: Eased<f32>,
+    /// How far the bar is slid out of the screen, from 0 (shown) to 1 (hidden).
+    slide: Eased<f32>,
+    /// A fullscreen window is shown on the output.
+    fullscreen: bool,
+    hovered: bool,
 
     config: Rc<Config>,
     workspaces: Workspaces,
     clock: Clock,
+    keyboard_layout: KeyboardLayout,
+    submap: Submap,
     sysmon: Sysmon,
     tray_view: TrayView,
+    window_title: WindowTitle,
 }
 
 impl Bar {
@@ -42,6 +53,15 @@ impl Bar {
         global_state: &GlobalState,
     ) -> (Self, Task<Message>) {
         let id = window::Id::unique();
+        let cfg = &global_state.config.bar;
+        let fullscreen = is_fullscreen(&output_name, &global_state.workspace_infos);
+        let slide = Eased::new(
+            slide_target_idx(cfg, fullscreen, false),
+            Easing::Smoothstep,
+            200.,
+            &[0.0, 1.0],
+        );
+        let margin = margin(cfg, slide.get(), fullscreen);
         (
             Self {
                 id,
@@ -49,29 +69,39 @@ impl Bar {
                 output_name: output_name.clone(),
                 size: None,
                 background_alpha_factor: Eased::new(
-                    alpha_target_idx(&output_name, &global_state.workspace_infos),
+                    alpha_target_idx(cfg, &output_name, &global_state.workspace_infos),
                     Easing::Smoothstep,
                     200.,
-                    &[1.0, 0.0],
+                    &[1.0, cfg.transparency.alpha.clamp(0., 1.)],
                 ),
+                slide,
+                fullscreen,
+                hovered: false,
 
                 config: global_state.config.clone(),
                 workspaces: Workspaces::new(output_name, global_state),
                 clock: Clock::new(global_state),
+                keyboard_layout: KeyboardLayout::new(global_state),
+                submap: Submap::new(global_state),
                 sysmon: Sysmon::new(global_state),
                 tray_view: TrayView::new(global_state),
+                window_title: WindowTitle::new(global_state),
             },
             get_layer_surface(SctkLayerSurfaceSettings {
                 id,
-                layer: Layer::Top,
+                layer: layer(cfg.layer),
                 keyboard_interactivity: KeyboardInteractivity::None,
                 input_zone: None,
-                anchor: Anchor::TOP | Anchor::LEFT | Anchor::RIGHT,
+                anchor: anchor(cfg.position),
                 output: IcedOutput::Output(wl_output),
-                namespace: "limbo:bar".to_string(),
-                margin: IcedMargin::default(),
-                size: Some((None, Some(40))),
-                exclusive_zone: 40,
+                namespace: cfg.namespace.clone(),
+                margin,
+                size: Some(if cfg.position.is_vertical() {
+                    (Some(cfg.size), None)
+                } else {
+                    (None, Some(cfg.size))
+                }),
+                exclusive_zone: cfg.exclusive_zone(),
                 size_limits: iced::Limits::NONE,
             }),
         )
@@ -81,87 +111,184 @@ impl Bar {
         destroy_layer_surface(self.id)
     }
 
-    pub fn update(&mut self, message: &Message) {
+    pub fn update(&mut self, message: &Message) -> Task<Message> {
         self.workspaces.update(message);
         self.clock.update(message);
+        self.keyboard_layout.update(message);
+        self.submap.update(message);
         self.sysmon.update(message);
         self.tray_view.update(message);
+        self.window_title.update(message);
         match message {
             Message::AnimationTick => {
                 self.background_alpha_factor.update();
+                if self.slide.is_running() {
+                    self.slide.update();
+                    return self.margin_task();
+                }
             }
             Message::WorkspacesChanged(workspace_infos) => {
                 self.background_alpha_factor
-                    .set_target_idx(alpha_target_idx(&self.output_name, workspace_infos));
+                    .set_target_idx(alpha_target_idx(
+                        &self.config.bar,
+                        &self.output_name,
+                        workspace_infos,
+                    ));
+                self.fullscreen = is_fullscreen(&self.output_name, workspace_infos);
+                self.update_slide();
             }
             Message::Iced(window_id, Event::Window(window::Event::Opened { size, .. }))
                 if *window_id == self.id =>
             {
                 self.size = Some(*size)
             }
+            Message::Iced(window_id, Event::Mouse(mouse::Event::CursorEntered))
+                if *window_id == self.id =>
+            {
+                self.hovered = true;
+                self.update_slide();
+            }
+            Message::Iced(window_id, Event::Mouse(mouse::Event::CursorLeft))
+                if *window_id == self.id =>
+            {
+                self.hovered = false;
+                self.update_slide();
+            }
             _ => (),
         };
+        Task::none()
+    }
+
+    fn update_slide(&mut self) {
+        self.slide.set_target_idx(slide_target_idx(
+            &self.config.bar,
+            self.fullscreen,
+            self.hovered,
+        ));
+    }
+
+    fn margin_task(&self) -> Task<Message> {
+        let margin = margin(&self.config.bar, self.slide.get(), self.fullscreen);
+        set_margin(
+            self.id,
+            margin.top,
+            margin.right,
+            margin.bottom,
+            margin.left,
+        )
     }
 
     pub fn view(&self) -> Element<'_, Message> {
         let background_alpha_factor = self.background_alpha_factor.get();
 
-        let mk_side = |modules: &Vec<ModuleName>| {
-            Row::from_iter(modules.iter().map(|module| {
-                match module {
-                    ModuleName::AppLauncher => self
-                        .config
-                        .section(icon("nix-snowflake-white", None))
-                        .into(),
-                    ModuleName::Battery => self
-                        .config
-                        .section(icon("nix-snowflake-white", None))
-                        .into(),
-                    ModuleName::Clock => self.clock.view(),
-                    ModuleName::Music => self
-                        .config
-                        .section(icon("nix-snowflake-white", None))
-                        .into(),
-                    ModuleName::Notifications => self
-                        .config
-                        .section(icon("nix-snowflake-white", None))
-                        .into(),
-                    // NOTE: temporary until full quick settings is impemented
-                    ModuleName::QuickSettings => self.tray_view.view(),
-                    ModuleName::Sysmon => self.sysmon.view(),
-                    ModuleName::Todo => self
-                        .config
-                        .section(icon("nix-snowflake-white", None))
-                        .into(),
-                    ModuleName::Workspaces => self.workspaces.view(),
-                }
-            }))
-            .spacing(12)
+        let cfg = &self.config.bar;
+        let vertical = cfg.position.is_vertical();
+        let [across, along] = cfg.padding;
+        let padding = if vertical {
+            [along, across]
+        } else {
+            [across, along]
         };
 
-        let left = mk_side(&self.config.bar.modules.left);
-        let center = mk_side(&self.config.bar.modules.center);
-        let right = mk_side(&self.config.bar.modules.right);
-
-        container(
-            row![
-                side(Alignment::Start, left),
-                side(Alignment::Center, center),
-                side(Alignment::End, right),
-            ]
-            .padding([4, 8])
-            .width(Length::Fill)
-            .height(Length::Fill),
-        )
-        .style(move |theme: &Theme| {
-            iced::widget::container::background(
-                theme
-                    .palette()
-                    .background
-                    .scale_alpha(background_alpha_factor),
-            )
-        })
-        .into()
+        let background = move |theme: &Theme| {
+            theme
+                .palette()
+                .background
+                .scale_alpha(background_alpha_factor)
+        };
+
+        let mk_side = |alignment: Alignment, modules: &Vec<ModuleName>| {
+            let modules = modules
+                .iter()
+                .filter(|module| !self.is_hidden(**module))
+                .collect::<Vec<_>>();
+            let content = self.config.flow(
+                cfg.spacing,
+                modules.iter().map(|module| {
+                    match module {
+                        ModuleName::AppLauncher => self
+                            .config
+                            .section(icon("nix-snowflake-white", None))
+                            .into(),
+                        ModuleName::Battery => self
+                            .config
+                            .section(icon("nix-snowflake-white", None))
+                            .into(),
+                        ModuleName::Clock => self.clock.view(),
+                        ModuleName::KeyboardLayout => self.keyboard_layout.view(),
+                        ModuleName::Music => self
+                            .config
+                            .section(icon("nix-snowflake-white", None))
+                            .into(),
+                        ModuleName::Notifications => self
+                            .config
+                            .section(icon("nix-snowflake-white", None))
+                            .into(),
+                        // NOTE: temporary until full quick settings is impemented
+                        ModuleName::QuickSettings => self.tray_view.view(),
+                        ModuleName::Submap => self.submap.view(),
+                        ModuleName::Sysmon => self.sysmon.view(),
+                        ModuleName::Todo => self
+                            .config
+                            .section(icon("nix-snowflake-white", None))
+                            .into(),
+                        ModuleName::WindowTitle => self.window_title.view(),
+                        ModuleName::Workspaces => self.workspaces.view(),
+                    }
+                }),
+            );
+
+            let content = if cfg.islands && !modules.is_empty() {
+                let radius = iced::Radius::new(self.config.theme.border_radius);
+                container(content)
+                    .padding(padding)
+                    .style(move |theme: &Theme| container::Style {
+                        background: Some(background(theme).into()),
+                        border: Border {
+                            radius,
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    })
+                    .into()
+            } else {
+                content
+            };
+
+            side(vertical, alignment, content)
+        };
+
+        let left = mk_side(Alignment::Start, &cfg.modules.left);
+        let center = mk_side(Alignment::Center, &cfg.modules.center);
+        let right = mk_side(Alignment::End, &cfg.modules.right);
+
+        // Islands carry their own padding and float directly on the transparent bar.
+        let bar_padding = if cfg.islands { [0, 0] } else { padding };
+
+        let sides: Element<'_, Message> = if vertical {
+            column![left, center, right]
+                .padding(bar_padding)
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .into()
+        } else {
+            row![left, center, right]
+                .padding(bar_padding)
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .into()
+        };
+
+        let islands = cfg.islands;
+        container(sides)
+            .style(move |theme: &Theme| {
+                if islands {
+                    container::Style::default()
+                } else {
+                    container::background(background(theme))
+                }
+            })
+            .into()
     }
 
     pub fn subscription(&self) -> iced::Subscription<Message> {
@@ -174,20 +301,196 @@ impl Bar {
         self.size.is_some()
     }
 
+    /// Whether `module` has nothing to show, e.g. because the desktop backend doesn't provide it.
+    fn is_hidden(&self, module: ModuleName) -> bool {
+        match module {
+            ModuleName::KeyboardLayout => self.keyboard_layout.is_empty(),
+            ModuleName::Submap => self.submap.is_empty(),
+            ModuleName::Workspaces => self.workspaces.is_empty(),
+            ModuleName::WindowTitle => self.window_title.is_empty(),
+            _ => false,
+        }
+    }
+
     pub fn animation_running(&self) -> bool {
-        self.background_alpha_factor.is_running() || self.workspaces.animation_running()
+        self.background_alpha_factor.is_running()
+            || self.slide.is_running()
+            || self.workspaces.animation_running()
+            || self.sysmon.animation_running()
+    }
+}
+
+fn layer(layer: BarLayer) -> Layer {
+    match layer {
+        BarLayer::Background => Layer::Background,
+        BarLayer::Bottom => Layer::Bottom,
+        BarLayer::Top => Layer::Top,
+        BarLayer::Overlay => Layer::Overlay,
+    }
+}
+
+fn anchor(position: BarPosition) -> Anchor {
+    match position {
+        BarPosition::Top => Anchor::TOP | Anchor::LEFT | Anchor::RIGHT,
+        BarPosition::Bottom => Anchor::BOTTOM | Anchor::LEFT | Anchor::RIGHT,
+        BarPosition::Left => Anchor::LEFT | Anchor::TOP | Anchor::BOTTOM,
+        BarPosition::Right => Anchor::RIGHT | Anchor::TOP | Anchor::BOTTOM,
     }
 }
 
-fn alpha_target_idx(output_name: &String, workspace_infos: &[WorkspaceInfo]) -> usize {
-    if workspace_infos
+fn slide_target_idx(cfg: &types::Bar, fullscreen: bool, hovered: bool) -> usize {
+    let hidden = (cfg.hide_on_fullscreen && fullscreen) || (cfg.auto_hide && !hovered);
+    if hidden { 1 } else { 0 }
+}
+
+/// Layer surface margin for a bar slid out by `slide`, moving it past its screen edge.
+fn margin(cfg: &types::Bar, slide: f32, fullscreen: bool) -> IcedMargin {
+    // A bar hidden for a fullscreen window can't be revealed on hover
+    let strip = if cfg.auto_hide && !(cfg.hide_on_fullscreen && fullscreen) {
+        REVEAL_STRIP
+    } else {
+        0
+    };
+    let distance = cfg.size as i32 + cfg.edge_margin() - strip;
+    let offset = (slide * distance as f32).round() as i32;
+    let m = &cfg.margin;
+    let (top, right, bottom, left) = match cfg.position {
+        BarPosition::Top => (m.top - offset, m.right, m.bottom, m.left),
+        BarPosition::Bottom => (m.top, m.right, m.bottom - offset, m.left),
+        BarPosition::Left => (m.top, m.right, m.bottom, m.left - offset),
+        BarPosition::Right => (m.top, m.right - offset, m.bottom, m.left),
+    };
+    IcedMargin {
+        top,
+        right,
+        bottom,
+        left,
+    }
+}
+
+fn is_fullscreen(output_name: &String, workspace_infos: &[WorkspaceInfo]) -> bool {
+    workspace_infos
         .iter()
-        .filter(|w| w.output.as_ref() == Some(output_name))
-        .find(|w| w.is_active)
-        .is_some_and(|w| w.transparent_bar)
-    {
+        .any(|w| w.output.as_ref() == Some(output_name) && w.is_active && w.has_fullscreen)
+}
+
+fn alpha_target_idx(
+    cfg: &types::Bar,
+    output_name: &String,
+    workspace_infos: &[WorkspaceInfo],
+) -> usize {
+    // Several workspaces can be visible at once, e.g. a special workspace over a regular one
+    let mut active = workspace_infos
+        .iter()
+        .filter(|w| w.output.as_ref() == Some(output_name) && w.is_active)
+        .peekable();
+    if active.peek().is_some() && active.all(|w| is_transparent(cfg, w)) {
         1
     } else {
         0
     }
 }
+
+fn is_transparent(cfg: &types::Bar, workspace: &WorkspaceInfo) -> bool {
+    let transparency = &cfg.transparency;
+    // Overrides are looked up by name first, then by id
+    let policy = workspace
+        .name
+        .as_ref()
+        .and_then(|name| transparency.workspaces.get(name))
+        .or_else(|| transparency.workspaces.get(&workspace.id.to_string()))
+        .copied()
+        .unwrap_or(transparency.policy);
+    match policy {
+        TransparencyPolicy::Always => true,
+        TransparencyPolicy::Never => false,
+        // Stay opaque rather than guess
+        TransparencyPolicy::WhenEmpty | TransparencyPolicy::WhenNoTiled
+            if workspace.windows_unknown =>
+        {
+            false
+        }
+        TransparencyPolicy::WhenEmpty => !workspace.has_windows,
+        TransparencyPolicy::WhenNoTiled => !workspace.has_tiled_windows,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use iced::futures::StreamExt;
+
+    use super::*;
+    use crate::desktop_environment::DesktopBackend;
+    use crate::desktop_environment::mock_desktop::MockDesktop;
+    use crate::desktop_environment::mock_desktop::tests::workspace;
+
+    #[tokio::test]
+    async fn transparency_follows_the_active_workspace() {
+        let mut cfg = Config::default().bar;
+        cfg.transparency
+            .workspaces
+            .insert("3".to_string(), TransparencyPolicy::Never);
+        let output = "DP-1".to_string();
+        let mut mock = MockDesktop::new(
+            vec![
+                vec![
+                    workspace("DP-1", 1, true, false),
+                    workspace("DP-1", 2, false, true),
+                    workspace("DP-1", 3, false, false),
+                ],
+                // A floating window only
+                vec![
+                    WorkspaceInfo {
+                        has_tiled_windows: false,
+                        ..workspace("DP-1", 1, true, true)
+                    },
+                    workspace("DP-1", 2, false, true),
+                    workspace("DP-1", 3, false, false),
+                ],
+            ],
+            Duration::from_millis(1),
+        );
+        let mut events = Box::pin(mock.events());
+
+        let empty = events.next().await.unwrap();
+        assert_eq!(alpha_target_idx(&cfg, &output, &empty), 1);
+        let floating = events.next().await.unwrap();
+        assert_eq!(alpha_target_idx(&cfg, &output, &floating), 1);
+
+        mock.focus_workspace(2);
+        let tiled = events.next().await.unwrap();
+        assert_eq!(alpha_target_idx(&cfg, &output, &tiled), 0);
+
+        // Overridden by id
+        mock.focus_workspace(3);
+        let overridden = events.next().await.unwrap();
+        assert_eq!(alpha_target_idx(&cfg, &output, &overridden), 0);
+
+        // Nothing active on another output
+        assert_eq!(
+            alpha_target_idx(&cfg, &"HDMI-A-1".to_string(), &overridden),
+            0
+        );
+        assert_eq!(*mock.focus_calls().lock().unwrap(), [2, 3]);
+    }
+
+    #[test]
+    fn unknown_windows_keep_the_bar_opaque() {
+        let mut cfg = Config::default().bar;
+        let unknown = WorkspaceInfo {
+            windows_unknown: true,
+            ..workspace("DP-1", 1, true, false)
+        };
+        for policy in [
+            TransparencyPolicy::WhenEmpty,
+            TransparencyPolicy::WhenNoTiled,
+        ] {
+            cfg.transparency.policy = policy;
+            assert!(!is_transparent(&cfg, &unknown), "{policy:?}");
+        }
+        cfg.transparency.policy = TransparencyPolicy::Always;
+        assert!(is_transparent(&cfg, &unknown));
+    }
+}
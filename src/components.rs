@@ -2,43 +2,64 @@ use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::{LazyLock, Mutex};
 
+use iced::advanced::layout::{self, Layout};
+use iced::advanced::widget::{self, Widget};
+use iced::advanced::{mouse, renderer};
 use iced::widget::svg::{Handle, Svg};
-use iced::widget::{Container, container, image, row, svg, text};
-use iced::{Alignment, Border, Color, Element, Length, Theme};
+use iced::widget::{Column, Container, Row, column, container, image, row, svg, text};
+use iced::{Alignment, Border, Color, Element, Length, Pixels, Rectangle, Size, Theme};
 
 use crate::config::Config;
 use crate::icons::{Icons, IconsFilled};
 
+/// One of the bar's left/center/right areas, filling its share of the bar's main axis.
 pub fn side<'a, Message>(
+    vertical: bool,
     alignment: Alignment,
     content: impl Into<iced::Element<'a, Message>>,
 ) -> Container<'a, Message> {
-    container(content).width(Length::Fill).align_x(alignment)
+    if vertical {
+        container(content).height(Length::Fill).align_y(alignment)
+    } else {
+        container(content).width(Length::Fill).align_x(alignment)
+    }
 }
 
 pub fn text_with_icon<'a, Message: 'a>(
+    vertical: bool,
     _icon: &'a str,
     color: Option<Color>,
     _text: impl text::IntoFragment<'a>,
 ) -> iced::Element<'a, Message> {
     let icon = icon(_icon, color);
     let text = text(_text);
-    row![icon, text]
-        .spacing(6)
-        .align_y(Alignment::Center)
-        .into()
+    if vertical {
+        column![icon, text]
+            .spacing(2)
+            .align_x(Alignment::Center)
+            .into()
+    } else {
+        row![icon, text]
+            .spacing(6)
+            .align_y(Alignment::Center)
+            .into()
+    }
 }
 
 static ICON_CACHE: LazyLock<Mutex<HashMap<String, Option<PathBuf>>>> =
     LazyLock::new(|| Mutex::new(HashMap::new()));
 pub fn system_icon<'a, Message>(name: &str) -> Option<Element<'a, Message>> {
-    let icon_path = ICON_CACHE
+    Some(image(image::Handle::from_path(system_icon_path(name)?)).into())
+}
+
+/// Looks up an icon in the icon theme, caching the result.
+pub fn system_icon_path(name: &str) -> Option<PathBuf> {
+    ICON_CACHE
         .lock()
         .ok()?
         .entry(name.to_string())
         .or_insert_with(|| freedesktop_icons::lookup(name).with_size(48).find())
-        .clone()?;
-    Some(image(image::Handle::from_path(icon_path)).into())
+        .clone()
 }
 
 pub fn icon(name: &'_ str, color: Option<Color>) -> Svg<'static> {
@@ -62,6 +83,121 @@ pub fn icon_filled(name: &'_ str, color: Option<Color>) -> Svg<'static> {
     .width(Length::Shrink)
 }
 
+/// A graph of values drawn as bars, e.g. recent samples of a metric or the usage of every core.
+pub struct Sparkline {
+    /// Values relative to the maximum, from 0 to 1.
+    values: Vec<f32>,
+    /// Number of bars the width is divided in, so that graphs fill up from the right.
+    slots: usize,
+    width: f32,
+    height: f32,
+    color: Color,
+}
+
+impl Sparkline {
+    pub fn new(values: impl IntoIterator<Item = f32>, max: f32) -> Self {
+        let values = values
+            .into_iter()
+            .map(|value| (value / max).clamp(0., 1.))
+            .collect::<Vec<_>>();
+        Self {
+            slots: values.len(),
+            values,
+            width: 32.,
+            height: 14.,
+            color: Color::WHITE,
+        }
+    }
+
+    /// Reserves room for `slots` bars, leaving the left empty until there are enough values.
+    pub fn slots(mut self, slots: usize) -> Self {
+        self.slots = slots.max(self.values.len());
+        self
+    }
+
+    pub fn width(mut self, width: f32) -> Self {
+        self.width = width;
+        self
+    }
+
+    pub fn height(mut self, height: f32) -> Self {
+        self.height = height;
+        self
+    }
+
+    pub fn color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+}
+
+impl<Message, Theme, Renderer> Widget<Message, Theme, Renderer> for Sparkline
+where
+    Renderer: renderer::Renderer,
+{
+    fn size(&self) -> Size<Length> {
+        Size::new(Length::Fixed(self.width), Length::Fixed(self.height))
+    }
+
+    fn layout(
+        &self,
+        _tree: &mut widget::Tree,
+        _renderer: &Renderer,
+        limits: &layout::Limits,
+    ) -> layout::Node {
+        layout::atomic(limits, self.width, self.height)
+    }
+
+    fn draw(
+        &self,
+        _tree: &widget::Tree,
+        renderer: &mut Renderer,
+        _theme: &Theme,
+        _style: &renderer::Style,
+        layout: Layout<'_>,
+        _cursor: mouse::Cursor,
+        _viewport: &Rectangle,
+    ) {
+        if self.slots == 0 {
+            return;
+        }
+        let bounds = layout.bounds();
+        let slot = bounds.width / self.slots as f32;
+        // Leave a gap between bars when they are wide enough
+        let gap = if slot >= 3. { 1. } else { 0. };
+        let offset = self.slots - self.values.len();
+        for (i, value) in self.values.iter().enumerate() {
+            // Keep a sliver visible for zero values, so the graph's extent stays readable
+            let height = (value * bounds.height).max(1.);
+            renderer.fill_quad(
+                renderer::Quad {
+                    bounds: Rectangle {
+                        x: bounds.x + (offset + i) as f32 * slot,
+                        y: bounds.y + bounds.height - height,
+                        width: slot - gap,
+                        height,
+                    },
+                    border: Border {
+                        radius: 1.0.into(),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                self.color,
+            );
+        }
+    }
+}
+
+impl<'a, Message, Theme, Renderer> From<Sparkline> for Element<'a, Message, Theme, Renderer>
+where
+    Renderer: renderer::Renderer + 'a,
+{
+    fn from(sparkline: Sparkline) -> Self {
+        Element::new(sparkline)
+    }
+}
+
 impl Config {
     pub fn section<'a, Message>(
         &self,
@@ -74,18 +210,46 @@ impl Config {
         ));
         let radius = iced::Radius::new(self.theme.border_radius);
 
-        container(content)
-            .style(move |_| container::Style {
-                background,
-                border: Border {
-                    radius,
-                    ..Default::default()
-                },
+        let section = container(content).style(move |_| container::Style {
+            background,
+            border: Border {
+                radius,
                 ..Default::default()
-            })
-            .padding([6, 12])
-            .align_y(Alignment::Center)
-            .height(Length::Fill)
+            },
+            ..Default::default()
+        });
+
+        if self.bar.position.is_vertical() {
+            section
+                .padding([12, 6])
+                .align_x(Alignment::Center)
+                .width(Length::Fill)
+        } else {
+            section
+                .padding([6, 12])
+                .align_y(Alignment::Center)
+                .height(Length::Fill)
+        }
+    }
+
+    /// Lays out `children` along the bar's main axis: a [`Row`] on horizontal bars and a
+    /// [`Column`] on vertical ones.
+    pub fn flow<'a, Message: 'a>(
+        &self,
+        spacing: impl Into<Pixels>,
+        children: impl IntoIterator<Item = Element<'a, Message>>,
+    ) -> Element<'a, Message> {
+        if self.bar.position.is_vertical() {
+            Column::with_children(children)
+                .spacing(spacing)
+                .align_x(Alignment::Center)
+                .into()
+        } else {
+            Row::with_children(children)
+                .spacing(spacing)
+                .align_y(Alignment::Center)
+                .into()
+        }
     }
 
     pub fn icon(&self, _icon: &crate::config::types::Icon) -> Svg<'static> {
@@ -101,6 +265,11 @@ impl Config {
         _icon: &'a crate::config::types::Icon,
         _text: impl text::IntoFragment<'a>,
     ) -> iced::Element<'a, Message> {
-        text_with_icon(&_icon.name, self.theme.resolve_color(&_icon.color), _text)
+        text_with_icon(
+            self.bar.position.is_vertical(),
+            &_icon.name,
+            self.theme.resolve_color(&_icon.color),
+            _text,
+        )
     }
 }
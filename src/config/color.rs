@@ -0,0 +1,404 @@
+use std::fmt;
+use std::str::FromStr;
+
+use color::{AlphaColor, Hsl, Srgb};
+use serde::{Deserialize, Serialize};
+
+use crate::animation::Lerpable;
+
+/// Wrapper for [`iced::Color`] implementing Serialize and Deserialize
+#[derive(Debug, Clone, Copy)]
+pub struct Color(pub iced::Color);
+
+impl Color {
+    pub fn parse(s: &str) -> Option<Self> {
+        iced::Color::parse(s).map(Self)
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let [r, g, b, a] = self.0.into_rgba8();
+        write!(f, "#{r:02X}{g:02X}{b:02X}")?;
+        if a != u8::MAX {
+            write!(f, "{a:02X}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for Color {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Color {
+    fn deserialize<D>(deserializer: D) -> Result<Color, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct ColorVisitor;
+        impl<'de> serde::de::Visitor<'de> for ColorVisitor {
+            type Value = Color;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "a color string")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                Color::parse(v).ok_or(serde::de::Error::custom("invalid color string"))
+            }
+        }
+        deserializer.deserialize_str(ColorVisitor)
+    }
+}
+
+/// A color as written in the config: a palette name (`"blue"`), a hex color (`"#89b4fa"`,
+/// `"#89b4facc"`), or an expression deriving a color from others, e.g. `"alpha(base, 0.8)"`,
+/// `"mix(blue, red, 0.3)"` or `"darken(surface0, 10%)"`.
+///
+/// Resolved against the palette with [`super::types::Theme::resolve_color`].
+#[derive(Debug, Clone)]
+pub enum ColorNameOrHex {
+    Name(String),
+    Hex(Color),
+    Expr(Box<ColorExpr>),
+}
+
+#[derive(Debug, Clone)]
+pub enum ColorExpr {
+    /// Replaces the alpha channel.
+    Alpha(ColorNameOrHex, f32),
+    /// Interpolates from the first to the second color in Lab space.
+    Mix(ColorNameOrHex, ColorNameOrHex, f32),
+    /// Decreases HSL lightness by an absolute amount.
+    Darken(ColorNameOrHex, f32),
+    /// Increases HSL lightness by an absolute amount.
+    Lighten(ColorNameOrHex, f32),
+}
+
+impl ColorNameOrHex {
+    pub fn name(name: impl Into<String>) -> Self {
+        ColorNameOrHex::Name(name.into())
+    }
+
+    /// Resolves the color, looking up names with `lookup`.
+    pub fn resolve(&self, lookup: &impl Fn(&str) -> Option<iced::Color>) -> Option<iced::Color> {
+        match self {
+            ColorNameOrHex::Name(name) => lookup(name),
+            ColorNameOrHex::Hex(color) => Some(color.0),
+            ColorNameOrHex::Expr(expr) => match expr.as_ref() {
+                ColorExpr::Alpha(color, alpha) => color.resolve(lookup).map(|c| iced::Color {
+                    a: alpha.clamp(0.0, 1.0),
+                    ..c
+                }),
+                ColorExpr::Mix(a, b, factor) => Some(Lerpable::lerp(
+                    &a.resolve(lookup)?,
+                    &b.resolve(lookup)?,
+                    factor.clamp(0.0, 1.0),
+                )),
+                ColorExpr::Darken(color, amount) => {
+                    Some(map_lightness(color.resolve(lookup)?, |l| l - amount))
+                }
+                ColorExpr::Lighten(color, amount) => {
+                    Some(map_lightness(color.resolve(lookup)?, |l| l + amount))
+                }
+            },
+        }
+    }
+}
+
+fn map_lightness(c: iced::Color, f: impl Fn(f32) -> f32) -> iced::Color {
+    let hsl: AlphaColor<Hsl> = AlphaColor::<Srgb>::new([c.r, c.g, c.b, c.a]).convert();
+    let srgb: AlphaColor<Srgb> = hsl.map_lightness(|l| f(l).clamp(0.0, 1.0)).convert();
+    let [r, g, b, a] = srgb.components;
+    iced::Color { r, g, b, a }
+}
+
+impl From<Color> for ColorNameOrHex {
+    fn from(color: Color) -> Self {
+        ColorNameOrHex::Hex(color)
+    }
+}
+
+impl FromStr for ColorNameOrHex {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+
+        if s.starts_with('#') {
+            return Color::parse(s)
+                .map(ColorNameOrHex::Hex)
+                .ok_or_else(|| format!("invalid hex color `{s}`"));
+        }
+
+        let Some((func, args)) = s.split_once('(') else {
+            if s.is_empty()
+                || !s
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+            {
+                return Err(format!("invalid color name `{s}`"));
+            }
+            return Ok(ColorNameOrHex::name(s));
+        };
+        let args = args
+            .strip_suffix(')')
+            .ok_or_else(|| format!("missing `)` in `{s}`"))?;
+        let args = split_args(args);
+
+        let expr = match (func.trim(), args.as_slice()) {
+            ("alpha", [color, alpha]) => ColorExpr::Alpha(color.parse()?, parse_amount(alpha)?),
+            ("mix", [a, b, factor]) => {
+                ColorExpr::Mix(a.parse()?, b.parse()?, parse_amount(factor)?)
+            }
+            ("mix", [a, b]) => ColorExpr::Mix(a.parse()?, b.parse()?, 0.5),
+            ("darken", [color, amount]) => ColorExpr::Darken(color.parse()?, parse_amount(amount)?),
+            ("lighten", [color, amount]) => {
+                ColorExpr::Lighten(color.parse()?, parse_amount(amount)?)
+            }
+            (func @ ("alpha" | "mix" | "darken" | "lighten"), args) => {
+                return Err(format!(
+                    "wrong number of arguments to `{func}`: got {}",
+                    args.len()
+                ));
+            }
+            (func, _) => return Err(format!("unknown color function `{func}`")),
+        };
+        Ok(ColorNameOrHex::Expr(Box::new(expr)))
+    }
+}
+
+/// Splits function arguments on commas that are not nested inside parentheses.
+fn split_args(args: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, c) in args.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                parts.push(args[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(args[start..].trim());
+    parts
+}
+
+/// Parses a fraction given either as a number (`0.1`) or a percentage (`10%`).
+fn parse_amount(s: &str) -> Result<f32, String> {
+    let invalid = |_| format!("invalid number `{s}`");
+    match s.strip_suffix('%') {
+        Some(percent) => percent
+            .trim()
+            .parse::<f32>()
+            .map(|p| p / 100.)
+            .map_err(invalid),
+        None => s.parse::<f32>().map_err(invalid),
+    }
+}
+
+impl fmt::Display for ColorNameOrHex {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ColorNameOrHex::Name(name) => write!(f, "{name}"),
+            ColorNameOrHex::Hex(color) => write!(f, "{color}"),
+            ColorNameOrHex::Expr(expr) => match expr.as_ref() {
+                ColorExpr::Alpha(color, alpha) => write!(f, "alpha({color}, {alpha})"),
+                ColorExpr::Mix(a, b, factor) => write!(f, "mix({a}, {b}, {factor})"),
+                ColorExpr::Darken(color, amount) => write!(f, "darken({color}, {amount})"),
+                ColorExpr::Lighten(color, amount) => write!(f, "lighten({color}, {amount})"),
+            },
+        }
+    }
+}
+
+impl Serialize for ColorNameOrHex {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ColorNameOrHex {
+    fn deserialize<D>(deserializer: D) -> Result<ColorNameOrHex, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct ColorNameOrHexVisitor;
+        impl<'de> serde::de::Visitor<'de> for ColorNameOrHexVisitor {
+            type Value = ColorNameOrHex;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "a color name, hex color or color expression")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                v.parse().map_err(serde::de::Error::custom)
+            }
+
+            // Accept the older `{ Name = "blue" }` and `{ Hex = "#89b4fa" }` forms
+            fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+            where
+                A: serde::de::MapAccess<'de>,
+            {
+                use serde::de::Error;
+
+                let (tag, value) = map
+                    .next_entry::<String, String>()?
+                    .ok_or_else(|| A::Error::custom("expected a `Name` or `Hex` key"))?;
+                match tag.as_str() {
+                    "Name" => Ok(ColorNameOrHex::Name(value)),
+                    "Hex" => Color::parse(&value)
+                        .map(ColorNameOrHex::Hex)
+                        .ok_or(A::Error::custom("invalid color string")),
+                    _ => Err(A::Error::unknown_field(&tag, &["Name", "Hex"])),
+                }
+            }
+        }
+        deserializer.deserialize_any(ColorNameOrHexVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::de::IntoDeserializer;
+    use serde::de::value::{Error as ValueError, MapDeserializer};
+
+    use super::*;
+
+    fn parse(s: &str) -> ColorNameOrHex {
+        s.parse()
+            .unwrap_or_else(|e| panic!("`{s}` should parse: {e}"))
+    }
+
+    fn resolve(color: &ColorNameOrHex) -> iced::Color {
+        let lookup = |name: &str| match name {
+            "black" => Some(iced::Color::BLACK),
+            "white" => Some(iced::Color::WHITE),
+            _ => None,
+        };
+        color.resolve(&lookup).expect("names are in the palette")
+    }
+
+    #[test]
+    fn parses_nested_expressions() {
+        let color = parse(" mix(alpha(black, 50%), #FFFFFF, 0.25) ");
+        assert_eq!(color.to_string(), "mix(alpha(black, 0.5), #FFFFFF, 0.25)");
+
+        let ColorNameOrHex::Expr(expr) = &color else {
+            panic!("expected an expression, got {color:?}");
+        };
+        let ColorExpr::Mix(ColorNameOrHex::Expr(inner), ColorNameOrHex::Hex(_), factor) = &**expr
+        else {
+            panic!("expected a mix of an expression and a hex color, got {expr:?}");
+        };
+        let ColorExpr::Alpha(ColorNameOrHex::Name(name), alpha) = &**inner else {
+            panic!("expected the alpha of a name, got {inner:?}");
+        };
+        assert_eq!((name.as_str(), *alpha, *factor), ("black", 0.5, 0.25));
+
+        let resolved = resolve(&color);
+        assert!((resolved.a - 0.625).abs() < 1e-3, "{resolved:?}");
+    }
+
+    #[test]
+    fn mix_defaults_to_halfway() {
+        assert_eq!(
+            parse("mix(black, white)").to_string(),
+            "mix(black, white, 0.5)"
+        );
+    }
+
+    #[test]
+    fn amounts_are_fractions_or_percentages() {
+        assert_eq!(parse_amount("0.1"), Ok(0.1));
+        assert_eq!(parse_amount("10%"), Ok(0.1));
+        assert_eq!(parse_amount("150 %"), Ok(1.5));
+        assert!(parse_amount("ten").is_err());
+        assert!(parse_amount("%").is_err());
+
+        let darker = resolve(&parse("darken(white, 25%)"));
+        let lighter = resolve(&parse("lighten(black, 0.25)"));
+        assert!((darker.r - 0.75).abs() < 1e-3, "{darker:?}");
+        assert!((lighter.r - 0.25).abs() < 1e-3, "{lighter:?}");
+    }
+
+    #[test]
+    fn splits_only_top_level_commas() {
+        assert_eq!(
+            split_args("alpha(blue, 0.5), mix(a, b, 1), 10%"),
+            ["alpha(blue, 0.5)", "mix(a, b, 1)", "10%"]
+        );
+        assert_eq!(split_args(""), [""]);
+    }
+
+    #[test]
+    fn rejects_malformed_colors() {
+        let error = |s: &str| s.parse::<ColorNameOrHex>().unwrap_err();
+        assert_eq!(error("#12345z"), "invalid hex color `#12345z`");
+        assert_eq!(error(""), "invalid color name ``");
+        assert_eq!(error("light blue"), "invalid color name `light blue`");
+        assert_eq!(error("alpha(blue, 0.5"), "missing `)` in `alpha(blue, 0.5`");
+        assert_eq!(
+            error("alpha(blue)"),
+            "wrong number of arguments to `alpha`: got 1"
+        );
+        assert_eq!(
+            error("mix(a, b, c, d)"),
+            "wrong number of arguments to `mix`: got 4"
+        );
+        assert_eq!(
+            error("saturate(blue, 0.1)"),
+            "unknown color function `saturate`"
+        );
+        assert_eq!(error("alpha(blue, half)"), "invalid number `half`");
+        assert_eq!(
+            error("mix(alpha(blue 0.5), red)"),
+            "wrong number of arguments to `alpha`: got 1"
+        );
+    }
+
+    #[test]
+    fn deserializes_strings_and_legacy_maps() {
+        let from_map = |key: &str, value: &str| {
+            let map = MapDeserializer::<_, ValueError>::new([(key, value)].into_iter());
+            ColorNameOrHex::deserialize(map)
+        };
+        let from_str = |s: &str| {
+            let deserializer: serde::de::value::StrDeserializer<'_, ValueError> =
+                s.into_deserializer();
+            ColorNameOrHex::deserialize(deserializer)
+        };
+
+        assert!(
+            matches!(from_map("Name", "blue"), Ok(ColorNameOrHex::Name(name)) if name == "blue")
+        );
+        assert_eq!(from_map("Hex", "#89b4fa").unwrap().to_string(), "#89B4FA");
+        assert!(from_map("Hex", "blue").is_err());
+        assert!(from_map("Expr", "blue").is_err());
+
+        assert_eq!(
+            from_str("alpha(#89b4fa, 80%)").unwrap().to_string(),
+            "alpha(#89B4FA, 0.8)"
+        );
+        assert!(from_str("alpha(#89b4fa)").is_err());
+    }
+}
@@ -1,8 +1,11 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use clap::Parser;
 use config::{Config as ConfigBuilder, ConfigError, Environment, File};
 
+mod color;
+mod palette;
+mod secret;
 pub mod types;
 pub use types::Config;
 
@@ -22,10 +25,22 @@ impl Config {
     pub fn load() -> Result<Self, ConfigError> {
         let args = Args::parse();
         let mut builder = ConfigBuilder::builder();
-        builder = builder.add_source(ConfigBuilder::try_from(&Self::default())?);
+
+        // The palette is merged in after loading, so that the default colors don't shadow the
+        // configured preset and palette file
+        let mut defaults = Self::default();
+        defaults.theme.colors.clear();
+        builder = builder.add_source(ConfigBuilder::try_from(&defaults)?);
+
+        // Relative paths in the config, such as `theme.paletteFile`, are relative to its directory
+        let config_dir;
 
         // If specific config file is provided via CLI, use only that
         if let Some(config_path) = args.config {
+            config_dir = config_path
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_default();
             builder = builder.add_source(File::from(config_path).required(true));
         } else {
             // Otherwise, try to load config files in order of preference
@@ -36,7 +51,7 @@ impl Config {
             ];
 
             // Load from ~/.config/limbo
-            let config_dir = dirs::config_dir()
+            config_dir = dirs::config_dir()
                 .map(|p| p.join("limbo"))
                 .or_else(|| dirs::home_dir().map(|p| p.join(".config").join("limbo")))
                 .ok_or_else(|| {
@@ -68,6 +83,8 @@ impl Config {
             builder = builder.set_override("general.debug", debug)?;
         }
 
-        builder.build()?.try_deserialize()
+        let mut config: Self = builder.build()?.try_deserialize()?;
+        config.theme.merge_palette(&config_dir)?;
+        Ok(config)
     }
 }
@@ -0,0 +1,462 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use config::{Config as ConfigBuilder, ConfigError, File, Value};
+use serde::{Deserialize, Serialize};
+
+use super::types::{Color, ColorNameOrHex, Theme};
+
+/// Built-in color schemes, using the Catppuccin color names throughout.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ThemePreset {
+    #[default]
+    Limbo,
+    CatppuccinLatte,
+    CatppuccinFrappe,
+    CatppuccinMacchiato,
+    CatppuccinMocha,
+    Gruvbox,
+    Nord,
+}
+
+impl ThemePreset {
+    pub fn colors(self) -> HashMap<String, Color> {
+        let palette = match self {
+            ThemePreset::Limbo => LIMBO,
+            ThemePreset::CatppuccinLatte => CATPPUCCIN_LATTE,
+            ThemePreset::CatppuccinFrappe => CATPPUCCIN_FRAPPE,
+            ThemePreset::CatppuccinMacchiato => CATPPUCCIN_MACCHIATO,
+            ThemePreset::CatppuccinMocha => CATPPUCCIN_MOCHA,
+            ThemePreset::Gruvbox => GRUVBOX,
+            ThemePreset::Nord => NORD,
+        };
+        let mut colors = palette
+            .iter()
+            .map(|(name, hex)| (name.to_string(), Color::parse(hex).unwrap()))
+            .collect();
+        fill_aliases(&mut colors);
+        colors
+    }
+}
+
+impl Theme {
+    /// Rebuilds `colors` from the preset and palette file, with the colors set in the config
+    /// taking precedence over both. A relative palette file is looked up in `config_dir`.
+    pub fn merge_palette(&mut self, config_dir: &Path) -> Result<(), ConfigError> {
+        let mut colors = self.preset.colors();
+        if let Some(path) = &self.palette_file {
+            colors.extend(load_palette_file(&config_dir.join(path))?);
+        }
+        colors.extend(std::mem::take(&mut self.colors));
+        self.colors = colors;
+        Ok(())
+    }
+}
+
+/// Loads a base16 or base24 scheme from a YAML, JSON or TOML file, in either the flat
+/// (`base00: "1e1e2e"`) or the tinted-theming (`palette: { base00: "#1e1e2e" }`) layout.
+///
+/// The raw `base00`..`base17` slots are kept alongside the names they are mapped onto.
+fn load_palette_file(path: &Path) -> Result<HashMap<String, Color>, ConfigError> {
+    let mut scheme = ConfigBuilder::builder()
+        .add_source(File::from(path).required(true))
+        .build()?
+        .try_deserialize::<HashMap<String, Value>>()?;
+
+    let slots = match scheme.remove("palette") {
+        Some(palette) => palette.into_table()?.into_iter().collect(),
+        None => scheme,
+    };
+
+    let mut colors = HashMap::new();
+    for (slot, value) in slots {
+        let slot = slot.to_lowercase();
+        if !is_base_slot(&slot) {
+            continue;
+        }
+        let hex = value.into_string()?;
+        let color = Color::parse(&hex).ok_or_else(|| {
+            ConfigError::Message(format!(
+                "invalid color `{hex}` for `{slot}` in {}",
+                path.display()
+            ))
+        })?;
+        colors.insert(slot, color);
+    }
+
+    if colors.is_empty() {
+        return Err(ConfigError::Message(format!(
+            "no base16 colors found in {}",
+            path.display()
+        )));
+    }
+
+    for (name, slots) in BASE16_SLOTS {
+        if let Some(color) = slots.iter().find_map(|slot| colors.get(*slot).copied()) {
+            colors.insert(name.to_string(), color);
+        }
+    }
+    fill_aliases(&mut colors);
+    Ok(colors)
+}
+
+fn is_base_slot(slot: &str) -> bool {
+    slot.len() == 6 && slot.starts_with("base") && slot[4..].chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Adds the colors that only some palettes define, derived from the ones they all do.
+fn fill_aliases(colors: &mut HashMap<String, Color>) {
+    for (name, expr) in ALIASES {
+        if colors.contains_key(*name) {
+            continue;
+        }
+        let expr: ColorNameOrHex = expr.parse().unwrap();
+        let color = expr.resolve(&|name| colors.get(name).map(|c| c.0));
+        if let Some(color) = color {
+            colors.insert(name.to_string(), Color(color));
+        }
+    }
+}
+
+/// Derived colors, in dependency order.
+const ALIASES: &[(&str, &str)] = &[
+    ("mantle", "darken(base, 3%)"),
+    ("crust", "darken(base, 6%)"),
+    ("core", "mix(base, surface0, 0.5)"),
+    ("overlay0", "mix(surface2, subtext0, 0.25)"),
+    ("overlay1", "mix(surface2, subtext0, 0.5)"),
+    ("overlay2", "mix(surface2, subtext0, 0.75)"),
+    ("subtext2", "text"),
+    ("cyan", "sky"),
+    ("lavenderDark", "darken(lavender, 15%)"),
+    ("blueDark", "darken(blue, 15%)"),
+    ("sapphireDark", "darken(sapphire, 15%)"),
+    ("skyDark", "darken(sky, 15%)"),
+    ("tealDark", "darken(teal, 15%)"),
+    ("greenDark", "darken(green, 15%)"),
+    ("yellowDark", "darken(yellow, 15%)"),
+    ("peachDark", "darken(peach, 15%)"),
+    ("maroonDark", "darken(maroon, 15%)"),
+    ("redDark", "darken(red, 15%)"),
+    ("mauveDark", "darken(mauve, 15%)"),
+];
+
+/// Palette names and the base16/base24 slots they are taken from, in order of preference.
+const BASE16_SLOTS: &[(&str, &[&str])] = &[
+    ("crust", &["base11"]),
+    ("mantle", &["base10"]),
+    ("base", &["base00"]),
+    ("surface0", &["base01"]),
+    ("surface1", &["base02"]),
+    ("surface2", &["base03"]),
+    ("subtext0", &["base04"]),
+    ("subtext1", &["base04"]),
+    ("text", &["base05"]),
+    ("rosewater", &["base06"]),
+    ("lavender", &["base07"]),
+    ("red", &["base08"]),
+    ("maroon", &["base12", "base08"]),
+    ("peach", &["base09"]),
+    ("yellow", &["base0a"]),
+    ("green", &["base0b"]),
+    ("teal", &["base0c"]),
+    ("sky", &["base15", "base0c"]),
+    ("sapphire", &["base0c"]),
+    ("blue", &["base0d"]),
+    ("mauve", &["base0e"]),
+    ("pink", &["base17", "base0e"]),
+    ("flamingo", &["base0f"]),
+];
+
+const LIMBO: &[(&str, &str)] = &[
+    ("crust", "#11111b"),
+    ("mantle", "#181825"),
+    ("base", "#1e1e2e"),
+    ("core", "#2c2c3f"),
+    ("surface0", "#313244"),
+    ("surface1", "#45475a"),
+    ("surface2", "#585b70"),
+    ("overlay0", "#6c7086"),
+    ("overlay1", "#7f849c"),
+    ("overlay2", "#9399b2"),
+    ("subtext0", "#a6adc8"),
+    ("subtext1", "#bac2de"),
+    ("subtext2", "#cdd6f4"),
+    ("text", "#f0f4ff"),
+    ("lavender", "#b4befe"),
+    ("lavenderDark", "#7f8cfe"),
+    ("blue", "#89b4fa"),
+    ("blueDark", "#5f8cfb"),
+    ("sapphire", "#74c7ec"),
+    ("sapphireDark", "#4a9edc"),
+    ("sky", "#89dceb"),
+    ("skyDark", "#5f9edc"),
+    ("teal", "#94e2d5"),
+    ("tealDark", "#5fb9a8"),
+    ("green", "#a6e3a1"),
+    ("greenDark", "#5fbf6b"),
+    ("yellow", "#f9e2af"),
+    ("yellowDark", "#f5c77b"),
+    ("peach", "#fab387"),
+    ("peachDark", "#f5a87b"),
+    ("maroon", "#eba0ac"),
+    ("maroonDark", "#c97b84"),
+    ("red", "#f38ba8"),
+    ("redDark", "#c97b84"),
+    ("mauve", "#cba6f7"),
+    ("mauveDark", "#a17be3"),
+    ("pink", "#f5c2e7"),
+    ("flamingo", "#f2cdcd"),
+    ("rosewater", "#f5e0dc"),
+    ("cyan", "#bee4ed"),
+];
+
+const CATPPUCCIN_LATTE: &[(&str, &str)] = &[
+    ("rosewater", "#dc8a78"),
+    ("flamingo", "#dd7878"),
+    ("pink", "#ea76cb"),
+    ("mauve", "#8839ef"),
+    ("red", "#d20f39"),
+    ("maroon", "#e64553"),
+    ("peach", "#fe640b"),
+    ("yellow", "#df8e1d"),
+    ("green", "#40a02b"),
+    ("teal", "#179299"),
+    ("sky", "#04a5e5"),
+    ("sapphire", "#209fb5"),
+    ("blue", "#1e66f5"),
+    ("lavender", "#7287fd"),
+    ("text", "#4c4f69"),
+    ("subtext1", "#5c5f77"),
+    ("subtext0", "#6c6f85"),
+    ("overlay2", "#7c7f93"),
+    ("overlay1", "#8c8fa1"),
+    ("overlay0", "#9ca0b0"),
+    ("surface2", "#acb0be"),
+    ("surface1", "#bcc0cc"),
+    ("surface0", "#ccd0da"),
+    ("base", "#eff1f5"),
+    ("mantle", "#e6e9ef"),
+    ("crust", "#dce0e8"),
+];
+
+const CATPPUCCIN_FRAPPE: &[(&str, &str)] = &[
+    ("rosewater", "#f2d5cf"),
+    ("flamingo", "#eebebe"),
+    ("pink", "#f4b8e4"),
+    ("mauve", "#ca9ee6"),
+    ("red", "#e78284"),
+    ("maroon", "#ea999c"),
+    ("peach", "#ef9f76"),
+    ("yellow", "#e5c890"),
+    ("green", "#a6d189"),
+    ("teal", "#81c8be"),
+    ("sky", "#99d1db"),
+    ("sapphire", "#85c1dc"),
+    ("blue", "#8caaee"),
+    ("lavender", "#babbf1"),
+    ("text", "#c6d0f5"),
+    ("subtext1", "#b5bfe2"),
+    ("subtext0", "#a5adce"),
+    ("overlay2", "#949cbb"),
+    ("overlay1", "#838ba7"),
+    ("overlay0", "#737994"),
+    ("surface2", "#626880"),
+    ("surface1", "#51576d"),
+    ("surface0", "#414559"),
+    ("base", "#303446"),
+    ("mantle", "#292c3c"),
+    ("crust", "#232634"),
+];
+
+const CATPPUCCIN_MACCHIATO: &[(&str, &str)] = &[
+    ("rosewater", "#f4dbd6"),
+    ("flamingo", "#f0c6c6"),
+    ("pink", "#f5bde6"),
+    ("mauve", "#c6a0f6"),
+    ("red", "#ed8796"),
+    ("maroon", "#ee99a0"),
+    ("peach", "#f5a97f"),
+    ("yellow", "#eed49f"),
+    ("green", "#a6da95"),
+    ("teal", "#8bd5ca"),
+    ("sky", "#91d7e3"),
+    ("sapphire", "#7dc4e4"),
+    ("blue", "#8aadf4"),
+    ("lavender", "#b7bdf8"),
+    ("text", "#cad3f5"),
+    ("subtext1", "#b8c0e0"),
+    ("subtext0", "#a5adcb"),
+    ("overlay2", "#939ab7"),
+    ("overlay1", "#8087a2"),
+    ("overlay0", "#6e738d"),
+    ("surface2", "#5b6078"),
+    ("surface1", "#494d64"),
+    ("surface0", "#363a4f"),
+    ("base", "#24273a"),
+    ("mantle", "#1e2030"),
+    ("crust", "#181926"),
+];
+
+const CATPPUCCIN_MOCHA: &[(&str, &str)] = &[
+    ("rosewater", "#f5e0dc"),
+    ("flamingo", "#f2cdcd"),
+    ("pink", "#f5c2e7"),
+    ("mauve", "#cba6f7"),
+    ("red", "#f38ba8"),
+    ("maroon", "#eba0ac"),
+    ("peach", "#fab387"),
+    ("yellow", "#f9e2af"),
+    ("green", "#a6e3a1"),
+    ("teal", "#94e2d5"),
+    ("sky", "#89dceb"),
+    ("sapphire", "#74c7ec"),
+    ("blue", "#89b4fa"),
+    ("lavender", "#b4befe"),
+    ("text", "#cdd6f4"),
+    ("subtext1", "#bac2de"),
+    ("subtext0", "#a6adc8"),
+    ("overlay2", "#9399b2"),
+    ("overlay1", "#7f849c"),
+    ("overlay0", "#6c7086"),
+    ("surface2", "#585b70"),
+    ("surface1", "#45475a"),
+    ("surface0", "#313244"),
+    ("base", "#1e1e2e"),
+    ("mantle", "#181825"),
+    ("crust", "#11111b"),
+];
+
+/// Gruvbox dark, medium contrast. The `*Dark` variants use the neutral accents.
+const GRUVBOX: &[(&str, &str)] = &[
+    ("rosewater", "#fbf1c7"),
+    ("flamingo", "#d3869b"),
+    ("pink", "#d3869b"),
+    ("mauve", "#d3869b"),
+    ("mauveDark", "#b16286"),
+    ("red", "#fb4934"),
+    ("redDark", "#cc241d"),
+    ("maroon", "#cc241d"),
+    ("peach", "#fe8019"),
+    ("peachDark", "#d65d0e"),
+    ("yellow", "#fabd2f"),
+    ("yellowDark", "#d79921"),
+    ("green", "#b8bb26"),
+    ("greenDark", "#98971a"),
+    ("teal", "#8ec07c"),
+    ("tealDark", "#689d6a"),
+    ("sky", "#8ec07c"),
+    ("sapphire", "#83a598"),
+    ("blue", "#83a598"),
+    ("blueDark", "#458588"),
+    ("lavender", "#83a598"),
+    ("text", "#ebdbb2"),
+    ("subtext1", "#d5c4a1"),
+    ("subtext0", "#bdae93"),
+    ("overlay2", "#a89984"),
+    ("overlay1", "#928374"),
+    ("overlay0", "#7c6f64"),
+    ("surface2", "#665c54"),
+    ("surface1", "#504945"),
+    ("surface0", "#3c3836"),
+    ("base", "#282828"),
+    ("crust", "#1d2021"),
+];
+
+const NORD: &[(&str, &str)] = &[
+    ("rosewater", "#eceff4"),
+    ("flamingo", "#d08770"),
+    ("pink", "#b48ead"),
+    ("mauve", "#b48ead"),
+    ("red", "#bf616a"),
+    ("maroon", "#bf616a"),
+    ("peach", "#d08770"),
+    ("yellow", "#ebcb8b"),
+    ("green", "#a3be8c"),
+    ("teal", "#8fbcbb"),
+    ("sky", "#88c0d0"),
+    ("sapphire", "#81a1c1"),
+    ("blue", "#81a1c1"),
+    ("blueDark", "#5e81ac"),
+    ("lavender", "#88c0d0"),
+    ("text", "#eceff4"),
+    ("subtext1", "#e5e9f0"),
+    ("subtext0", "#d8dee9"),
+    ("surface2", "#4c566a"),
+    ("surface1", "#434c5e"),
+    ("surface0", "#3b4252"),
+    ("base", "#2e3440"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A directory of its own for each test, holding a flat and a tinted-theming scheme.
+    fn scheme_dir(test: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("limbo-palette-{test}-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("flat.yaml"),
+            "scheme: Flat\nbase00: \"101010\"\nbase05: \"e0e0e0\"\nbase08: \"ff0000\"\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("tinted.yaml"),
+            "name: Tinted\npalette:\n  base00: \"#202020\"\n  base0D: \"#0000ff\"\n  \
+             base08: \"#aa0000\"\n",
+        )
+        .unwrap();
+        dir
+    }
+
+    fn hex(colors: &HashMap<String, Color>, name: &str) -> String {
+        colors[name].to_string()
+    }
+
+    #[test]
+    fn loads_flat_and_tinted_schemes() {
+        let dir = scheme_dir("layouts");
+
+        let flat = load_palette_file(&dir.join("flat.yaml")).unwrap();
+        assert_eq!(hex(&flat, "base"), "#101010");
+        assert_eq!(hex(&flat, "text"), "#E0E0E0");
+        assert_eq!(hex(&flat, "red"), "#FF0000");
+        // Slots are kept, and base24 names fall back to base16 slots
+        assert_eq!(hex(&flat, "base08"), "#FF0000");
+        assert_eq!(hex(&flat, "maroon"), "#FF0000");
+
+        let tinted = load_palette_file(&dir.join("tinted.yaml")).unwrap();
+        assert_eq!(hex(&tinted, "base"), "#202020");
+        assert_eq!(hex(&tinted, "blue"), "#0000FF");
+        assert!(!tinted.contains_key("name"));
+    }
+
+    #[test]
+    fn rejects_files_without_base16_colors() {
+        let dir = scheme_dir("empty");
+        std::fs::write(dir.join("empty.yaml"), "scheme: Empty\n").unwrap();
+        assert!(load_palette_file(&dir.join("empty.yaml")).is_err());
+        assert!(load_palette_file(&dir.join("missing.yaml")).is_err());
+    }
+
+    #[test]
+    fn config_colors_override_the_palette_file_and_preset() {
+        let dir = scheme_dir("override");
+        let mut theme = Theme {
+            preset: ThemePreset::Nord,
+            // Relative to the config directory, not the working directory
+            palette_file: Some("flat.yaml".into()),
+            colors: HashMap::from([("red".to_string(), Color::parse("#123456").unwrap())]),
+            ..Default::default()
+        };
+        theme.merge_palette(&dir).unwrap();
+
+        let nord = ThemePreset::Nord.colors();
+        assert_eq!(hex(&theme.colors, "red"), "#123456");
+        assert_eq!(hex(&theme.colors, "base"), "#101010");
+        // Left to the preset by the palette file
+        assert_eq!(hex(&theme.colors, "blue"), hex(&nord, "blue"));
+    }
+}
@@ -0,0 +1,272 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::OnceCell;
+use zbus::zvariant::{OwnedObjectPath, OwnedValue, Type, Value};
+
+/// A secret string that never shows up in `Debug` output.
+#[derive(Clone)]
+pub struct Secret(String);
+
+// Nothing sends the tokens anywhere yet.
+#[allow(dead_code)]
+impl Secret {
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Secret(<redacted>)")
+    }
+}
+
+/// An API token, given inline or loaded from a file, the output of a command, or the
+/// Secret Service. Only one source should be set; they are tried in the order below.
+///
+/// The token is loaded on first use and cached once loaded, so that e.g. a keyring that is still
+/// locked at login is tried again on the next use. Inline tokens are never serialized.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiToken {
+    #[serde(default, skip_serializing)]
+    pub api_token: Option<String>,
+    /// e.g. an agenix or sops-nix secret path.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_token_file: Option<PathBuf>,
+    /// Run with `sh -c`; the first line of its output is used, e.g. `pass show github`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_token_command: Option<String>,
+    /// Attributes identifying the item in the Secret Service, e.g.
+    /// `{ service = "github", username = "me" }` as stored by `secret-tool store`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_token_keyring: Option<HashMap<String, String>>,
+    #[serde(skip)]
+    resolved: OnceCell<Secret>,
+}
+
+impl fmt::Debug for ApiToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let ApiToken {
+            api_token,
+            api_token_file,
+            api_token_command,
+            api_token_keyring,
+            ..
+        } = self;
+        f.debug_struct("ApiToken")
+            .field("api_token", &api_token.as_ref().map(|_| "<redacted>"))
+            .field("api_token_file", &api_token_file)
+            .field("api_token_command", &api_token_command)
+            .field("api_token_keyring", &api_token_keyring)
+            .finish()
+    }
+}
+
+#[allow(dead_code)]
+impl ApiToken {
+    /// Loads the token until that succeeds, returning `None` if no source is configured or
+    /// loading failed.
+    pub async fn resolve(&self) -> Option<&Secret> {
+        let resolved = self
+            .resolved
+            .get_or_try_init(|| async {
+                match self.load().await {
+                    Ok(Some(secret)) => Ok(secret),
+                    Ok(None) => Err(None),
+                    Err(e) => Err(Some(e)),
+                }
+            })
+            .await;
+        match resolved {
+            Ok(secret) => Some(secret),
+            Err(None) => None,
+            Err(Some(e)) => {
+                eprintln!("Failed to load API token: {e}");
+                None
+            }
+        }
+    }
+
+    async fn load(&self) -> Result<Option<Secret>, String> {
+        if let Some(token) = &self.api_token {
+            return Ok(Some(Secret(token.trim().to_string())));
+        }
+
+        if let Some(path) = &self.api_token_file {
+            let token = tokio::fs::read_to_string(path)
+                .await
+                .map_err(|e| format!("reading {}: {e}", path.display()))?;
+            return Ok(Some(Secret(token.trim().to_string())));
+        }
+
+        if let Some(command) = &self.api_token_command {
+            let output = crate::shell::command(command)
+                .output()
+                .await
+                .map_err(|e| format!("running `{command}`: {e}"))?;
+            if !output.status.success() {
+                return Err(format!("`{command}` exited with {}", output.status));
+            }
+            let stdout = String::from_utf8(output.stdout)
+                .map_err(|_| format!("`{command}` printed invalid UTF-8"))?;
+            let token = stdout.lines().next().unwrap_or_default();
+            return Ok(Some(Secret(token.trim().to_string())));
+        }
+
+        if let Some(attributes) = &self.api_token_keyring {
+            return lookup_keyring(attributes)
+                .await
+                .map(Some)
+                .map_err(|e| format!("looking up secret service item: {e}"));
+        }
+
+        Ok(None)
+    }
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.Secret.Service",
+    default_service = "org.freedesktop.secrets",
+    default_path = "/org/freedesktop/secrets"
+)]
+trait SecretService {
+    fn open_session(
+        &self,
+        algorithm: &str,
+        input: &Value<'_>,
+    ) -> zbus::Result<(OwnedValue, OwnedObjectPath)>;
+
+    fn search_items(
+        &self,
+        attributes: HashMap<&str, &str>,
+    ) -> zbus::Result<(Vec<OwnedObjectPath>, Vec<OwnedObjectPath>)>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.Secret.Item",
+    default_service = "org.freedesktop.secrets"
+)]
+trait SecretItem {
+    fn get_secret(&self, session: &OwnedObjectPath) -> zbus::Result<SecretStruct>;
+}
+
+#[derive(Deserialize, Type)]
+struct SecretStruct {
+    _session: OwnedObjectPath,
+    _parameters: Vec<u8>,
+    value: Vec<u8>,
+    _content_type: String,
+}
+
+/// Reads the first unlocked Secret Service item matching `attributes`, over a plain
+/// (unencrypted) session on the session bus.
+async fn lookup_keyring(attributes: &HashMap<String, String>) -> zbus::Result<Secret> {
+    let connection = zbus::Connection::session().await?;
+    let service = SecretServiceProxy::new(&connection).await?;
+
+    let attributes = attributes
+        .iter()
+        .map(|(k, v)| (k.as_str(), v.as_str()))
+        .collect();
+    let (unlocked, locked) = service.search_items(attributes).await?;
+    let Some(item) = unlocked.into_iter().next() else {
+        return Err(zbus::Error::Failure(if locked.is_empty() {
+            "no matching item".to_string()
+        } else {
+            "matching item is locked".to_string()
+        }));
+    };
+
+    let (_, session) = service.open_session("plain", &Value::from("")).await?;
+    let item = SecretItemProxy::builder(&connection)
+        .path(item)?
+        .build()
+        .await?;
+    let secret = item.get_secret(&session).await?;
+
+    String::from_utf8(secret.value)
+        .map(|token| Secret(token.trim().to_string()))
+        .map_err(|_| zbus::Error::Failure("secret is not valid UTF-8".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("limbo-secret-{name}-{}", std::process::id()))
+    }
+
+    #[tokio::test]
+    async fn reads_trimmed_tokens_from_files() {
+        let path = temp_path("file");
+        std::fs::write(&path, "  file-token\n").unwrap();
+        let token = ApiToken {
+            api_token_file: Some(path.clone()),
+            ..Default::default()
+        };
+        assert_eq!(
+            token.resolve().await.map(Secret::expose),
+            Some("file-token")
+        );
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn reads_the_first_line_of_commands() {
+        let token = ApiToken {
+            api_token_command: Some("printf 'command-token \\nsecond line\\n'".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            token.resolve().await.map(Secret::expose),
+            Some("command-token")
+        );
+    }
+
+    #[tokio::test]
+    async fn retries_until_loading_succeeds() {
+        let path = temp_path("retry");
+        let _ = std::fs::remove_file(&path);
+        let token = ApiToken {
+            api_token_command: Some(format!("cat '{}'", path.display())),
+            ..Default::default()
+        };
+        assert!(token.resolve().await.is_none());
+
+        std::fs::write(&path, "late-token").unwrap();
+        assert_eq!(
+            token.resolve().await.map(Secret::expose),
+            Some("late-token")
+        );
+
+        // Cached once loaded
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            token.resolve().await.map(Secret::expose),
+            Some("late-token")
+        );
+    }
+
+    #[tokio::test]
+    async fn nothing_to_resolve_without_a_source() {
+        assert!(ApiToken::default().resolve().await.is_none());
+    }
+
+    #[test]
+    fn debug_output_hides_the_token() {
+        let token = ApiToken {
+            api_token: Some("hunter2".to_string()),
+            ..Default::default()
+        };
+        assert!(!format!("{token:?}").contains("hunter2"));
+        assert_eq!(
+            format!("{:?}", Secret("hunter2".to_string())),
+            "Secret(<redacted>)"
+        );
+    }
+}
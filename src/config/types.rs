@@ -1,7 +1,12 @@
 use std::collections::HashMap;
+use std::path::PathBuf;
 
 use serde::{Deserialize, Serialize};
 
+pub use super::color::{Color, ColorNameOrHex};
+pub use super::palette::ThemePreset;
+pub use super::secret::ApiToken;
+
 #[derive(Debug, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Config {
@@ -20,6 +25,24 @@ pub struct General {
     /// Get from <https://www.latlong.net/>
     pub lon: f64,
     pub debug: bool,
+    /// Skip compositor detection and use this backend. Overridden by `LIMBO_DESKTOP`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub desktop: Option<DesktopKind>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DesktopKind {
+    Hyprland,
+    Niri,
+    Sway,
+    River,
+    ExtWorkspace,
+    /// Replay workspaces from the JSON file at `LIMBO_MOCK_SCRIPT`, for working on the bar
+    /// without a compositor. Never detected automatically.
+    Mock,
+    /// Don't connect to any compositor, hiding the workspaces module.
+    None,
 }
 
 #[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
@@ -40,77 +63,31 @@ pub enum Unit {
     Imperial,
 }
 
-/// Wrapper for [`iced::Color`] implementing Serialize and Deserialize
-#[derive(Debug, Clone, Copy)]
-pub struct Color(pub iced::Color);
-
-impl Color {
-    pub fn parse(s: &str) -> Option<Self> {
-        iced::Color::parse(s).map(Self)
-    }
-}
-
-impl Serialize for Color {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: serde::Serializer,
-    {
-        let rgba = self.0.into_rgba8();
-        let s = format!("#{:02X}{:02X}{:02X}", rgba[0], rgba[1], rgba[2]); // ignore alpha
-        serializer.serialize_str(&s)
-    }
-}
-
-impl<'de> Deserialize<'de> for Color {
-    fn deserialize<D>(deserializer: D) -> Result<Color, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        struct ColorVisitor;
-        impl<'de> serde::de::Visitor<'de> for ColorVisitor {
-            type Value = Color;
-
-            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-                write!(f, "a color string")
-            }
-
-            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
-            where
-                E: serde::de::Error,
-            {
-                Color::parse(v).ok_or(serde::de::Error::custom("invalid color string"))
-            }
-        }
-        deserializer.deserialize_str(ColorVisitor)
-    }
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub enum ColorNameOrHex {
-    Name(String),
-    Hex(Color),
-}
-
-impl ColorNameOrHex {
-    pub fn name(name: impl Into<String>) -> Self {
-        ColorNameOrHex::Name(name.into())
-    }
-}
-
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Theme {
     pub font: String,
     pub border_radius: f32,
+    /// Base palette, see [`ThemePreset`].
+    pub preset: ThemePreset,
+    /// base16/base24 scheme (YAML, JSON or TOML) layered over the preset. Relative paths start
+    /// from the directory of the config file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub palette_file: Option<PathBuf>,
+    /// Colors added to or overriding the preset and palette file.
     pub colors: HashMap<String, Color>,
 }
 
 impl Theme {
+    /// Resolves palette names and color expressions. Names missing from the palette are
+    /// tried as bare hex colors.
     pub fn resolve_color(&self, color: &ColorNameOrHex) -> Option<iced::Color> {
-        match color {
-            ColorNameOrHex::Name(name) => self.colors.get(name).map(|c| c.0),
-            ColorNameOrHex::Hex(color) => Some(color.0),
-        }
+        color.resolve(&|name| {
+            self.colors
+                .get(name)
+                .map(|c| c.0)
+                .or_else(|| iced::Color::parse(name))
+        })
     }
 }
 
@@ -119,70 +96,185 @@ impl Default for Theme {
         Self {
             font: "IBM Plex Mono".to_string(),
             border_radius: 6.0,
-            colors: HashMap::from(
-                [
-                    ("crust", "#11111b"),
-                    ("mantle", "#181825"),
-                    ("base", "#1e1e2e"),
-                    ("core", "#2c2c3f"),
-                    ("surface0", "#313244"),
-                    ("surface1", "#45475a"),
-                    ("surface2", "#585b70"),
-                    ("overlay0", "#6c7086"),
-                    ("overlay1", "#7f849c"),
-                    ("overlay2", "#9399b2"),
-                    ("subtext0", "#a6adc8"),
-                    ("subtext1", "#bac2de"),
-                    ("subtext2", "#cdd6f4"),
-                    ("text", "#f0f4ff"),
-                    ("lavender", "#b4befe"),
-                    ("lavenderDark", "#7f8cfe"),
-                    ("blue", "#89b4fa"),
-                    ("blueDark", "#5f8cfb"),
-                    ("sapphire", "#74c7ec"),
-                    ("sapphireDark", "#4a9edc"),
-                    ("sky", "#89dceb"),
-                    ("skyDark", "#5f9edc"),
-                    ("teal", "#94e2d5"),
-                    ("tealDark", "#5fb9a8"),
-                    ("green", "#a6e3a1"),
-                    ("greenDark", "#5fbf6b"),
-                    ("yellow", "#f9e2af"),
-                    ("yellowDark", "#f5c77b"),
-                    ("peach", "#fab387"),
-                    ("peachDark", "#f5a87b"),
-                    ("maroon", "#eba0ac"),
-                    ("maroonDark", "#c97b84"),
-                    ("red", "#f38ba8"),
-                    ("redDark", "#c97b84"),
-                    ("mauve", "#cba6f7"),
-                    ("mauveDark", "#a17be3"),
-                    ("pink", "#f5c2e7"),
-                    ("flamingo", "#f2cdcd"),
-                    ("rosewater", "#f5e0dc"),
-                    ("cyan", "#bee4ed"),
-                ]
-                .map(|(name, hex)| (name.to_string(), Color::parse(hex).unwrap())),
-            ),
+            preset: ThemePreset::default(),
+            palette_file: None,
+            colors: ThemePreset::default().colors(),
         }
     }
 }
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Bar {
+    pub position: BarPosition,
+    /// Thickness of the bar in pixels.
+    pub size: u32,
+    /// Defaults to `size` plus the margin on the anchored edge.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exclusive_zone: Option<i32>,
+    pub layer: BarLayer,
+    pub margin: Margin,
+    /// `[across, along]` the bar's main axis.
+    pub padding: [u16; 2],
+    /// Space between modules.
+    pub spacing: u16,
+    /// Layer shell namespace, used by compositors for layer rules.
+    pub namespace: String,
+    /// Float the left, center and right modules as separate pills on a transparent bar.
+    pub islands: bool,
+    /// Slide the bar out while a fullscreen window is shown on its output.
+    ///
+    /// niri doesn't report fullscreen windows, so there a window counts as fullscreen when its
+    /// tile is as large as the output. A maximized window without gaps looks the same, and the
+    /// output sizes are only read again when outputs are added or removed, not when their mode
+    /// or scale changes.
+    pub hide_on_fullscreen: bool,
+    /// Keep the bar hidden without reserving space, except for a thin strip along the screen
+    /// edge that reveals it on hover.
+    pub auto_hide: bool,
+    pub transparency: Transparency,
     pub theme: BarTheme,
     pub modules: Modules,
     pub app_launcher: AppLauncher,
     pub battery: Battery,
     pub clock: Clock,
+    pub keyboard_layout: KeyboardLayout,
     pub notifications: Notifications,
     pub quick_settings: QuickSettings,
+    pub submap: Submap,
     pub sysmon: Sysmon,
     pub todo: Todo,
+    pub window_title: WindowTitle,
     pub workspaces: Workspaces,
 }
 
+impl Default for Bar {
+    fn default() -> Self {
+        Self {
+            position: Default::default(),
+            size: 40,
+            exclusive_zone: None,
+            layer: Default::default(),
+            margin: Default::default(),
+            padding: [4, 8],
+            spacing: 12,
+            namespace: "limbo:bar".to_string(),
+            islands: false,
+            hide_on_fullscreen: true,
+            auto_hide: false,
+            transparency: Default::default(),
+            theme: Default::default(),
+            modules: Default::default(),
+            app_launcher: Default::default(),
+            battery: Default::default(),
+            clock: Default::default(),
+            keyboard_layout: Default::default(),
+            notifications: Default::default(),
+            quick_settings: Default::default(),
+            submap: Default::default(),
+            sysmon: Default::default(),
+            todo: Default::default(),
+            window_title: Default::default(),
+            workspaces: Default::default(),
+        }
+    }
+}
+
+impl Bar {
+    /// Exclusive zone to reserve, including the margin between the bar and its screen edge.
+    pub fn exclusive_zone(&self) -> i32 {
+        // An auto-hidden bar is drawn over windows
+        if self.auto_hide {
+            return 0;
+        }
+        self.exclusive_zone
+            .unwrap_or_else(|| self.size as i32 + self.edge_margin())
+    }
+
+    /// Margin between the bar and the screen edge it's anchored to.
+    pub fn edge_margin(&self) -> i32 {
+        match self.position {
+            BarPosition::Top => self.margin.top,
+            BarPosition::Bottom => self.margin.bottom,
+            BarPosition::Left => self.margin.left,
+            BarPosition::Right => self.margin.right,
+        }
+    }
+}
+
+/// When the bar background fades out, depending on the active workspaces of its output.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Transparency {
+    pub policy: TransparencyPolicy,
+    /// Background opacity when transparent, from 0 to 1.
+    pub alpha: f32,
+    /// Policies for specific workspaces, by name or id. The name is looked up first, so a key
+    /// matching the name of one workspace and the id of another applies to the named one. On
+    /// Hyprland, regular workspaces are usually named after their id anyway.
+    pub workspaces: HashMap<String, TransparencyPolicy>,
+}
+
+impl Default for Transparency {
+    fn default() -> Self {
+        Self {
+            policy: Default::default(),
+            alpha: 0.,
+            workspaces: HashMap::new(),
+        }
+    }
+}
+
+/// When a workspace makes the bar transparent. With ext-workspace, which can't see windows,
+/// `when-empty` and `when-no-tiled` keep the bar opaque.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TransparencyPolicy {
+    Always,
+    Never,
+    /// Without any window.
+    WhenEmpty,
+    /// Without windows other than floating ones.
+    #[default]
+    WhenNoTiled,
+}
+
+/// Screen edge the bar is anchored to.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BarPosition {
+    #[default]
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+impl BarPosition {
+    /// Whether modules are laid out top to bottom instead of left to right.
+    pub fn is_vertical(self) -> bool {
+        matches!(self, Self::Left | Self::Right)
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BarLayer {
+    Background,
+    Bottom,
+    #[default]
+    Top,
+    Overlay,
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct Margin {
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+    pub left: i32,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct BarTheme {
@@ -207,11 +299,14 @@ pub enum ModuleName {
     AppLauncher,
     Battery,
     Clock,
+    KeyboardLayout,
     Music,
     Notifications,
     QuickSettings,
+    Submap,
     Sysmon,
     Todo,
+    WindowTitle,
     Workspaces,
 }
 
@@ -338,6 +433,44 @@ impl Default for Clock {
     }
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyboardLayout {
+    pub icon: Icon,
+    /// Short names by the compositor's layout name, e.g. `"English (US)" = "us"`. Other layouts
+    /// are shortened to their first two letters.
+    pub short_names: HashMap<String, String>,
+}
+
+impl Default for KeyboardLayout {
+    fn default() -> Self {
+        Self {
+            icon: Icon::new("keyboard", ColorNameOrHex::name("text")),
+            short_names: [
+                ("English (US)", "us"),
+                ("English (Colemak)", "colemak"),
+                ("German", "de"),
+            ]
+            .into_iter()
+            .map(|(name, short_name)| (name.to_string(), short_name.to_string()))
+            .collect(),
+        }
+    }
+}
+
+impl KeyboardLayout {
+    pub fn short_name(&self, layout: &str) -> String {
+        self.short_names.get(layout).cloned().unwrap_or_else(|| {
+            layout
+                .chars()
+                .filter(|c| c.is_alphabetic())
+                .take(2)
+                .collect::<String>()
+                .to_lowercase()
+        })
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum NotificationSegment {
@@ -441,7 +574,8 @@ pub struct Todoist {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub text: Option<Text>,
     /// Get from <https://todoist.com/prefs/integrations>
-    pub api_token: String,
+    #[serde(flatten)]
+    pub api_token: ApiToken,
     #[serde(flatten)]
     pub mouse_commands: MouseCommands,
 }
@@ -451,7 +585,7 @@ impl Default for Todoist {
         Self {
             icon: Icon::new("checkbox", ColorNameOrHex::name("red")),
             text: None,
-            api_token: String::new(),
+            api_token: Default::default(),
             mouse_commands: Default::default(),
         }
     }
@@ -464,7 +598,8 @@ pub struct Github {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub text: Option<Text>,
     /// Classic token with the 'notifications' scope
-    pub api_token: String,
+    #[serde(flatten)]
+    pub api_token: ApiToken,
     #[serde(flatten)]
     pub mouse_commands: MouseCommands,
 }
@@ -474,7 +609,7 @@ impl Default for Github {
         Self {
             icon: Icon::new("brand-github", ColorNameOrHex::name("text")),
             text: None,
-            api_token: String::new(),
+            api_token: Default::default(),
             mouse_commands: MouseCommands {
                 on_primary_click: Some("xdg-open https://github.com/notifications".to_string()),
                 ..Default::default()
@@ -804,12 +939,29 @@ impl Default for Toggle {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum SysmonSegment {
     Cpu,
     Temp,
     Ram,
+    Gpu,
+    Disk,
+    Net,
+}
+
+/// Shown while a Hyprland submap other than the default one is active.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Submap {
+    pub icon: Icon,
+}
+
+impl Default for Submap {
+    fn default() -> Self {
+        Self {
+            icon: Icon::new("command", ColorNameOrHex::name("peach")),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -817,9 +969,14 @@ pub enum SysmonSegment {
 pub struct Sysmon {
     pub segments: Vec<SysmonSegment>,
     pub probe_interval_ms: u64,
+    /// Number of samples kept for graphs.
+    pub history_length: usize,
     pub cpu: Cpu,
     pub temp: Temp,
     pub ram: Ram,
+    pub gpu: Gpu,
+    pub disk: Disk,
+    pub net: Net,
     #[serde(flatten)]
     pub mouse_commands: MouseCommands,
 }
@@ -829,18 +986,30 @@ impl Default for Sysmon {
         Self {
             segments: vec![SysmonSegment::Cpu, SysmonSegment::Temp, SysmonSegment::Ram],
             probe_interval_ms: 5000,
+            history_length: 30,
             cpu: Default::default(),
             temp: Default::default(),
             ram: Default::default(),
+            gpu: Default::default(),
+            disk: Default::default(),
+            net: Default::default(),
             mouse_commands: Default::default(),
         }
     }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Cpu {
     pub icon: Icon,
     pub precision: usize,
+    /// Show a graph of recent usage.
+    pub graph: bool,
+    /// Show the current usage of every core as bars.
+    pub per_core: bool,
+    /// Usage in percent.
+    #[serde(flatten)]
+    pub thresholds: Thresholds,
 }
 
 impl Default for Cpu {
@@ -848,14 +1017,32 @@ impl Default for Cpu {
         Self {
             icon: Icon::new("cpu", ColorNameOrHex::name("lavender")),
             precision: 1,
+            graph: false,
+            per_core: false,
+            thresholds: Default::default(),
         }
     }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Temp {
     pub icon: Icon,
     pub precision: usize,
+    /// Show a graph of recent temperatures.
+    pub graph: bool,
+    /// Temperature at the top of the graph, in the unit of `general.unit`. Defaults to the
+    /// critical threshold, or to 100 °C without one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub graph_max: Option<f32>,
+    /// Labels of the sensors to read, where `*` and `?` are wildcards. The first ones found are
+    /// AMD Zen's control temperature and Intel's package temperature.
+    pub sensor: Vec<String>,
+    /// How the readings are combined when several sensors match.
+    pub aggregation: TempAggregation,
+    /// Temperature in the unit of `general.unit`, like the displayed value.
+    #[serde(flatten)]
+    pub thresholds: Thresholds,
 }
 
 impl Default for Temp {
@@ -863,14 +1050,38 @@ impl Default for Temp {
         Self {
             icon: Icon::new("temperature", ColorNameOrHex::name("red")),
             precision: 0,
+            graph: false,
+            graph_max: None,
+            sensor: vec![
+                "k10temp Tctl".to_string(),
+                "coretemp Package id 0".to_string(),
+            ],
+            aggregation: Default::default(),
+            thresholds: Default::default(),
         }
     }
 }
 
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TempAggregation {
+    /// The hottest sensor.
+    #[default]
+    Max,
+    /// The average of the sensors.
+    Avg,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Ram {
     pub icon: Icon,
     pub precision: usize,
+    /// Show a graph of recent usage.
+    pub graph: bool,
+    /// Usage in percent of the total memory.
+    #[serde(flatten)]
+    pub thresholds: Thresholds,
 }
 
 impl Default for Ram {
@@ -878,10 +1089,167 @@ impl Default for Ram {
         Self {
             icon: Icon::new("cpu-2", ColorNameOrHex::name("pink")),
             precision: 1,
+            graph: false,
+            thresholds: Default::default(),
         }
     }
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Gpu {
+    pub icon: Icon,
+    pub precision: usize,
+    /// DRM card to read, e.g. `card1`. Defaults to the first card with stats.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub card: Option<String>,
+    pub source: GpuSource,
+    pub show_vram: bool,
+    pub show_temp: bool,
+    /// Usage in percent.
+    #[serde(flatten)]
+    pub thresholds: Thresholds,
+}
+
+impl Default for Gpu {
+    fn default() -> Self {
+        Self {
+            icon: Icon::new("device-desktop-analytics", ColorNameOrHex::name("green")),
+            precision: 0,
+            card: None,
+            source: Default::default(),
+            show_vram: true,
+            show_temp: false,
+            thresholds: Default::default(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Disk {
+    pub mounts: Vec<DiskMount>,
+    /// Icon of mounts without their own.
+    pub icon: Icon,
+    pub precision: usize,
+    /// Show free space along with used and total space.
+    pub show_free: bool,
+    /// Show read and write throughput of the mount's device.
+    pub show_io: bool,
+    /// Used space in percent.
+    #[serde(flatten)]
+    pub thresholds: Thresholds,
+}
+
+impl Default for Disk {
+    fn default() -> Self {
+        Self {
+            mounts: vec![DiskMount {
+                path: PathBuf::from("/"),
+                icon: None,
+                warning: None,
+                critical: None,
+            }],
+            icon: Icon::new("device-floppy", ColorNameOrHex::name("sapphire")),
+            precision: 0,
+            show_free: false,
+            show_io: false,
+            thresholds: Thresholds {
+                warning: Some(80.),
+                critical: Some(90.),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiskMount {
+    pub path: PathBuf,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon: Option<Icon>,
+    /// Overrides `warning` for this mount.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warning: Option<f32>,
+    /// Overrides `critical` for this mount.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub critical: Option<f32>,
+}
+
+/// Values from which a sysmon segment is shown in the warning and critical colors.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Thresholds {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warning: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub critical: Option<f32>,
+    pub warning_color: ColorNameOrHex,
+    pub critical_color: ColorNameOrHex,
+    /// Blink while critical.
+    pub blink: bool,
+    /// Run with `sh -c` whenever the value crosses into critical.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub critical_command: Option<String>,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Self {
+            warning: None,
+            critical: None,
+            warning_color: ColorNameOrHex::name("yellow"),
+            critical_color: ColorNameOrHex::name("red"),
+            blink: false,
+            critical_command: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Net {
+    /// Interface to show, e.g. `wlan0`. Defaults to the interface of the default route.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interface: Option<String>,
+    /// Shown when the interface is missing or there's no default route.
+    pub icon: Icon,
+    pub down_icon: Icon,
+    pub up_icon: Icon,
+    /// Pad rates to a constant width in a monospace font, so the bar doesn't shift as they change.
+    pub fixed_width: bool,
+    /// Rate in bytes per second, of the faster of download and upload.
+    #[serde(flatten)]
+    pub thresholds: Thresholds,
+}
+
+impl Default for Net {
+    fn default() -> Self {
+        Self {
+            interface: None,
+            icon: Icon::new("network", ColorNameOrHex::name("teal")),
+            down_icon: Icon::new("arrow-down", ColorNameOrHex::name("teal")),
+            up_icon: Icon::new("arrow-up", ColorNameOrHex::name("peach")),
+            fixed_width: false,
+            thresholds: Default::default(),
+        }
+    }
+}
+
+/// Where GPU stats are read from.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GpuSource {
+    /// sysfs, falling back to `nvidia-smi`.
+    #[default]
+    Auto,
+    /// The DRM and hwmon sysfs interfaces of the amdgpu, i915 and xe drivers.
+    Sysfs,
+    /// `nvidia-smi`, for the proprietary NVIDIA driver.
+    NvidiaSmi,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Todo {
@@ -900,9 +1268,135 @@ impl Default for Todo {
     }
 }
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowTitle {
+    /// Titles longer than this many characters are cut off with an ellipsis.
+    pub max_length: usize,
+    /// Show the focused app's icon from the icon theme.
+    pub app_icon: bool,
+    /// Shown in place of the app icon when it can't be found.
+    pub fallback_icon: Icon,
+    /// Shown when no window is focused, e.g. on an empty workspace.
+    pub empty: EmptyWindowTitle,
+    /// Applied in order to the title of matching apps.
+    pub rewrites: Vec<TitleRewrite>,
+}
+
+impl Default for WindowTitle {
+    fn default() -> Self {
+        Self {
+            max_length: 50,
+            app_icon: true,
+            fallback_icon: Icon::new("app-window", ColorNameOrHex::name("text")),
+            empty: Default::default(),
+            rewrites: vec![TitleRewrite {
+                app_id: Some("firefox|librewolf|zen".to_string()),
+                title: " [—-] (Mozilla Firefox|LibreWolf|Zen Browser)$".to_string(),
+                replacement: String::new(),
+            }],
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EmptyWindowTitle {
+    pub icon: Icon,
+    pub text: String,
+}
+
+impl Default for EmptyWindowTitle {
+    fn default() -> Self {
+        Self {
+            icon: Icon::new("device-desktop", ColorNameOrHex::name("text")),
+            text: "Desktop".to_string(),
+        }
+    }
+}
+
+/// Rewrites titles matching the `title` regex, e.g. to strip a browser's name. `replacement` may
+/// refer to capture groups as `$1` or `${name}`.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TitleRewrite {
+    /// Regex matched against the app id (Wayland) or class (X11); matches every app if unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub app_id: Option<String>,
+    pub title: String,
+    pub replacement: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Workspaces {
     pub color: WorkspaceColors,
+    pub label: WorkspaceLabel,
+    /// Maximum number of app icons per workspace with the `icons` label.
+    pub max_icons: usize,
+    pub scroll: WorkspaceScroll,
+    /// Clicks other than the left one do nothing unless configured.
+    pub middle_click: WorkspaceAction,
+    pub right_click: WorkspaceAction,
+}
+
+impl Default for Workspaces {
+    fn default() -> Self {
+        Self {
+            color: Default::default(),
+            label: Default::default(),
+            max_icons: 3,
+            scroll: Default::default(),
+            middle_click: WorkspaceAction::None,
+            right_click: WorkspaceAction::None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceScroll {
+    /// Pixels to scroll before switching workspaces. Each wheel notch switches once.
+    pub threshold: f32,
+    /// Wrap around from the last workspace to the first one and vice versa.
+    pub wrap: bool,
+}
+
+impl Default for WorkspaceScroll {
+    fn default() -> Self {
+        Self {
+            threshold: 50.,
+            wrap: false,
+        }
+    }
+}
+
+/// Action run when clicking a workspace pill.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WorkspaceAction {
+    #[default]
+    None,
+    /// Switch to the workspace.
+    Focus,
+    /// Move the focused window to the workspace, without following it.
+    MoveWindow,
+    /// Open or close niri's overview.
+    ToggleOverview,
+}
+
+/// Content drawn inside each workspace pill.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WorkspaceLabel {
+    /// An empty pill.
+    #[default]
+    None,
+    /// The workspace name, falling back to its index.
+    Name,
+    /// The 1-based position of the workspace on its output.
+    Index,
+    /// Icons of the apps open on the workspace.
+    Icons,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -911,6 +1405,10 @@ pub struct WorkspaceColors {
     pub active: ColorNameOrHex,
     pub has_windows: ColorNameOrHex,
     pub normal: ColorNameOrHex,
+    /// Special (scratchpad) workspaces, whether shown or not.
+    pub special: ColorNameOrHex,
+    /// Pulsed on workspaces with a window requesting attention, until they are visited.
+    pub urgent: ColorNameOrHex,
 }
 
 impl Default for WorkspaceColors {
@@ -919,6 +1417,8 @@ impl Default for WorkspaceColors {
             active: ColorNameOrHex::name("blue"),
             has_windows: ColorNameOrHex::name("blue"),
             normal: ColorNameOrHex::name("surface2"),
+            special: ColorNameOrHex::name("mauve"),
+            urgent: ColorNameOrHex::name("red"),
         }
     }
 }
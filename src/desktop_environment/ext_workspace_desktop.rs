@@ -0,0 +1,465 @@
+//! Compositor-agnostic backend using the `ext-workspace-v1` Wayland protocol.
+//!
+//! The protocol knows nothing about windows, so workspaces are published with `windows_unknown`
+//! and without windows, `app_ids` or fullscreen.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use sctk::reexports::client::backend::ObjectId;
+use sctk::reexports::client::globals::{GlobalListContents, registry_queue_init};
+use sctk::reexports::client::protocol::wl_output::{self, WlOutput};
+use sctk::reexports::client::protocol::wl_registry::{self, WlRegistry};
+use sctk::reexports::client::{
+    Connection, Dispatch, Proxy, QueueHandle, WEnum, event_created_child,
+};
+use sctk::reexports::protocols::ext::workspace::v1::client::ext_workspace_group_handle_v1::{
+    self, ExtWorkspaceGroupHandleV1,
+};
+use sctk::reexports::protocols::ext::workspace::v1::client::ext_workspace_handle_v1::{
+    self, ExtWorkspaceHandleV1,
+};
+use sctk::reexports::protocols::ext::workspace::v1::client::ext_workspace_manager_v1::{
+    self, ExtWorkspaceManagerV1,
+};
+use tokio::sync::watch;
+
+use super::{DesktopBackend, WorkspaceId, WorkspaceInfo};
+use crate::message::Message;
+
+pub struct ExtWorkspaceDesktop {
+    connection: Connection,
+    manager: ExtWorkspaceManagerV1,
+    /// The workspaces last sent to `rx`, along with their handles.
+    workspaces: Workspaces,
+    rx: Arc<tokio::sync::Mutex<watch::Receiver<Vec<WorkspaceInfo>>>>,
+}
+
+impl ExtWorkspaceDesktop {
+    /// Connects to the Wayland display, returning `None` if the compositor does not support
+    /// `ext-workspace-v1`. Events are dispatched on a separate thread.
+    pub fn connect() -> Option<Self> {
+        let connection = Connection::connect_to_env().ok()?;
+        let (globals, mut queue) = registry_queue_init::<State>(&connection).ok()?;
+        let qh = queue.handle();
+        let manager = globals
+            .bind::<ExtWorkspaceManagerV1, _, _>(&qh, 1..=1, ())
+            .ok()?;
+
+        let (tx, rx) = watch::channel(Vec::new());
+        let workspaces = Workspaces::default();
+        let mut state = State {
+            outputs: HashMap::new(),
+            groups: HashMap::new(),
+            workspaces: Vec::new(),
+            next_idx: 0,
+            published: workspaces.clone(),
+            tx,
+        };
+        globals.contents().with_list(|list| {
+            for global in list {
+                if global.interface == WlOutput::interface().name {
+                    bind_output(globals.registry(), global.name, global.version, &qh);
+                }
+            }
+        });
+
+        std::thread::spawn(move || {
+            while queue.blocking_dispatch(&mut state).is_ok() {}
+            eprintln!("ext-workspace event queue closed");
+        });
+
+        Some(Self {
+            connection,
+            manager,
+            workspaces,
+            rx: Arc::new(tokio::sync::Mutex::new(rx)),
+        })
+    }
+
+    fn activate(&self, handle: &ExtWorkspaceHandleV1) {
+        handle.activate();
+        self.manager.commit();
+        let _ = self.connection.flush();
+    }
+}
+
+impl DesktopBackend for ExtWorkspaceDesktop {
+    fn focus_workspace(&mut self, id: WorkspaceId) {
+        let workspaces = self.workspaces.lock().unwrap();
+        if let Some((_, handle)) = workspaces.iter().find(|(info, _)| info.id == id) {
+            self.activate(handle);
+        }
+    }
+
+    fn subscription(&self) -> iced::Subscription<Message> {
+        #[derive(Hash)]
+        struct ExtWorkspaceEvents;
+
+        iced::Subscription::run_with_id(
+            ExtWorkspaceEvents,
+            iced::futures::stream::unfold(self.rx.clone(), |rx| async move {
+                let value = {
+                    let mut rx = rx.lock().await;
+                    if rx.changed().await.is_ok() {
+                        Some(rx.borrow().clone())
+                    } else {
+                        None
+                    }
+                };
+                value.map(|v| (Message::WorkspacesChanged(v), rx))
+            }),
+        )
+    }
+}
+
+fn bind_output(registry: &WlRegistry, name: u32, version: u32, qh: &QueueHandle<State>) {
+    // `wl_output.name` was added in version 4
+    if version >= 4 {
+        registry.bind::<WlOutput, _, _>(name, 4, qh, ());
+    }
+}
+
+type Workspaces = Arc<Mutex<Vec<(WorkspaceInfo, ExtWorkspaceHandleV1)>>>;
+
+#[derive(Default)]
+struct Group {
+    outputs: Vec<ObjectId>,
+}
+
+struct Workspace {
+    handle: ExtWorkspaceHandleV1,
+    group: Option<ObjectId>,
+    /// Order in which the compositor announced the workspace, used when it has no coordinates.
+    idx: i32,
+    name: Option<String>,
+    coordinates: Vec<u32>,
+    state: ext_workspace_handle_v1::State,
+}
+
+struct State {
+    outputs: HashMap<ObjectId, String>,
+    groups: HashMap<ObjectId, Group>,
+    workspaces: Vec<Workspace>,
+    next_idx: i32,
+    published: Workspaces,
+    tx: watch::Sender<Vec<WorkspaceInfo>>,
+}
+
+impl State {
+    fn workspace_mut(&mut self, handle: &ExtWorkspaceHandleV1) -> Option<&mut Workspace> {
+        self.workspaces.iter_mut().find(|w| &w.handle == handle)
+    }
+
+    /// Sends the current state, called once the compositor is done with a batch of changes.
+    fn publish(&self) {
+        use ext_workspace_handle_v1::State as WorkspaceState;
+
+        let mut workspaces = self
+            .workspaces
+            .iter()
+            .filter(|w| !w.state.contains(WorkspaceState::Hidden))
+            .collect::<Vec<_>>();
+        workspaces.sort_by_key(|w| (w.coordinates.clone(), w.idx));
+
+        let output_name = |w: &Workspace| {
+            let group = self.groups.get(w.group.as_ref()?)?;
+            group
+                .outputs
+                .iter()
+                .find_map(|output| self.outputs.get(output).cloned())
+        };
+
+        // Number the workspaces of each output separately, from 1
+        let mut next_idx = HashMap::<Option<String>, i32>::new();
+        let published = workspaces
+            .iter()
+            .map(|w| {
+                let output = output_name(w);
+                let idx = next_idx.entry(output.clone()).or_default();
+                *idx += 1;
+                let info = WorkspaceInfo {
+                    output,
+                    id: w.handle.id().protocol_id() as WorkspaceId,
+                    idx: *idx,
+                    name: w.name.clone(),
+                    app_ids: Vec::new(),
+                    is_active: w.state.contains(WorkspaceState::Active),
+                    is_urgent: w.state.contains(WorkspaceState::Urgent),
+                    has_windows: false,
+                    has_fullscreen: false,
+                    has_tiled_windows: false,
+                    windows_unknown: true,
+                    is_special: false,
+                };
+                (info, w.handle.clone())
+            })
+            .collect::<Vec<_>>();
+
+        let workspace_infos = published.iter().map(|(info, _)| info.clone()).collect();
+        *self.published.lock().unwrap() = published;
+        let _ = self.tx.send(workspace_infos);
+    }
+}
+
+impl Dispatch<WlRegistry, GlobalListContents> for State {
+    fn event(
+        state: &mut Self,
+        registry: &WlRegistry,
+        event: wl_registry::Event,
+        _: &GlobalListContents,
+        _: &Connection,
+        qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            wl_registry::Event::Global {
+                name,
+                interface,
+                version,
+            } if interface == WlOutput::interface().name => {
+                bind_output(registry, name, version, qh);
+            }
+            wl_registry::Event::GlobalRemove { .. } => {
+                // Outputs are released along with their workspace groups.
+                state.publish();
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<WlOutput, ()> for State {
+    fn event(
+        state: &mut Self,
+        output: &WlOutput,
+        event: wl_output::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let wl_output::Event::Name { name } = event {
+            state.outputs.insert(output.id(), name);
+        }
+    }
+}
+
+impl Dispatch<ExtWorkspaceManagerV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        _: &ExtWorkspaceManagerV1,
+        event: ext_workspace_manager_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        use ext_workspace_manager_v1::Event;
+        match event {
+            Event::WorkspaceGroup { workspace_group } => {
+                state.groups.insert(workspace_group.id(), Group::default());
+            }
+            Event::Workspace { workspace } => {
+                state.workspaces.push(Workspace {
+                    handle: workspace,
+                    group: None,
+                    idx: state.next_idx,
+                    name: None,
+                    coordinates: Vec::new(),
+                    state: ext_workspace_handle_v1::State::empty(),
+                });
+                state.next_idx += 1;
+            }
+            Event::Done => state.publish(),
+            _ => {}
+        }
+    }
+
+    event_created_child!(State, ExtWorkspaceManagerV1, [
+        ext_workspace_manager_v1::EVT_WORKSPACE_GROUP_OPCODE => (ExtWorkspaceGroupHandleV1, ()),
+        ext_workspace_manager_v1::EVT_WORKSPACE_OPCODE => (ExtWorkspaceHandleV1, ()),
+    ]);
+}
+
+impl Dispatch<ExtWorkspaceGroupHandleV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        group: &ExtWorkspaceGroupHandleV1,
+        event: ext_workspace_group_handle_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        use ext_workspace_group_handle_v1::Event;
+        match event {
+            Event::OutputEnter { output } => {
+                if let Some(g) = state.groups.get_mut(&group.id()) {
+                    g.outputs.push(output.id());
+                }
+            }
+            Event::OutputLeave { output } => {
+                if let Some(g) = state.groups.get_mut(&group.id()) {
+                    g.outputs.retain(|o| *o != output.id());
+                }
+            }
+            Event::WorkspaceEnter { workspace } => {
+                if let Some(w) = state.workspace_mut(&workspace) {
+                    w.group = Some(group.id());
+                }
+            }
+            Event::WorkspaceLeave { workspace } => {
+                if let Some(w) = state.workspace_mut(&workspace) {
+                    w.group = None;
+                }
+            }
+            Event::Removed => {
+                state.groups.remove(&group.id());
+                group.destroy();
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<ExtWorkspaceHandleV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        handle: &ExtWorkspaceHandleV1,
+        event: ext_workspace_handle_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        use ext_workspace_handle_v1::Event;
+        match event {
+            Event::Name { name } => {
+                if let Some(w) = state.workspace_mut(handle) {
+                    w.name = Some(name);
+                }
+            }
+            Event::Coordinates { coordinates } => {
+                if let Some(w) = state.workspace_mut(handle) {
+                    w.coordinates = coordinates
+                        .chunks_exact(4)
+                        .map(|c| u32::from_ne_bytes([c[0], c[1], c[2], c[3]]))
+                        .collect();
+                }
+            }
+            Event::State {
+                state: WEnum::Value(workspace_state),
+            } => {
+                if let Some(w) = state.workspace_mut(handle) {
+                    w.state = workspace_state;
+                }
+            }
+            Event::Removed => {
+                state.workspaces.retain(|w| &w.handle != handle);
+                handle.destroy();
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::net::UnixStream;
+
+    use super::*;
+
+    /// Queue for the registries handing out object ids.
+    struct Ids;
+
+    impl Dispatch<WlRegistry, ()> for Ids {
+        fn event(
+            _: &mut Self,
+            _: &WlRegistry,
+            _: wl_registry::Event,
+            _: &(),
+            _: &Connection,
+            _: &QueueHandle<Self>,
+        ) {
+        }
+    }
+
+    #[test]
+    fn publish_numbers_workspaces_per_output() {
+        // Nothing reads the requests, the connection only gives out ids for groups and outputs
+        let (socket, _compositor) = UnixStream::pair().unwrap();
+        let connection = Connection::from_socket(socket).unwrap();
+        let queue = connection.new_event_queue::<Ids>();
+        let new_id = || connection.display().get_registry(&queue.handle(), ()).id();
+        let (dp, hdmi, left, right) = (new_id(), new_id(), new_id(), new_id());
+
+        use ext_workspace_handle_v1::State as WorkspaceState;
+        let workspace =
+            |group: &ObjectId, idx, name: &str, coordinates: Vec<u32>, state| Workspace {
+                handle: ExtWorkspaceHandleV1::inert(connection.backend().downgrade()),
+                group: Some(group.clone()),
+                idx,
+                name: Some(name.to_string()),
+                coordinates,
+                state,
+            };
+        let (tx, rx) = watch::channel(Vec::new());
+        let state = State {
+            outputs: HashMap::from([
+                (dp.clone(), "DP-1".to_string()),
+                (hdmi.clone(), "HDMI-A-1".to_string()),
+            ]),
+            groups: HashMap::from([
+                (left.clone(), Group { outputs: vec![dp] }),
+                (
+                    right.clone(),
+                    Group {
+                        outputs: vec![hdmi],
+                    },
+                ),
+            ]),
+            workspaces: vec![
+                workspace(&left, 0, "2", vec![1], WorkspaceState::empty()),
+                workspace(&right, 1, "web", Vec::new(), WorkspaceState::Active),
+                workspace(&left, 2, "1", vec![0], WorkspaceState::Active),
+                workspace(&left, 3, "hidden", vec![2], WorkspaceState::Hidden),
+                workspace(&right, 4, "chat", Vec::new(), WorkspaceState::Urgent),
+            ],
+            next_idx: 5,
+            published: Default::default(),
+            tx,
+        };
+
+        state.publish();
+        let workspace_infos = rx.borrow().clone();
+        let summary = workspace_infos
+            .iter()
+            .map(|w| {
+                (
+                    w.output.as_deref().unwrap(),
+                    w.idx,
+                    w.name.as_deref().unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            [
+                ("HDMI-A-1", 1, "web"),
+                ("HDMI-A-1", 2, "chat"),
+                ("DP-1", 1, "1"),
+                ("DP-1", 2, "2")
+            ]
+        );
+        let flags = workspace_infos
+            .iter()
+            .map(|w| (w.is_active, w.is_urgent))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            flags,
+            [(true, false), (false, true), (true, false), (false, false)]
+        );
+        assert!(
+            workspace_infos
+                .iter()
+                .all(|w| w.windows_unknown && !w.has_windows && !w.has_tiled_windows)
+        );
+        assert_eq!(state.published.lock().unwrap().len(), 4);
+    }
+}
@@ -1,13 +1,16 @@
-use hyprland::data::{Clients, Monitors, WorkspaceRules, Workspaces};
+use std::collections::HashSet;
+
+use hyprland::ctl::switch_xkb_layout::{self, SwitchXKBLayoutCmdTypes};
+use hyprland::data::{Client, Clients, Devices, Monitors, WorkspaceRules, Workspaces};
 use hyprland::dispatch;
 use hyprland::dispatch::WorkspaceIdentifierWithSpecial;
 use hyprland::error::HyprError;
 use hyprland::event_listener::{Event as HyprEvent, EventStream};
-use hyprland::shared::HyprData;
+use hyprland::shared::{HyprData, HyprDataActiveOptional};
 use iced::futures::StreamExt;
-use iced::futures::stream::once;
+use iced::futures::stream::{self, BoxStream, unfold};
 
-use super::{WorkspaceId, WorkspaceInfo};
+use super::{Backoff, DesktopBackend, WindowInfo, WorkspaceId, WorkspaceInfo};
 use crate::message::Message;
 
 pub struct HyprlandDesktop;
@@ -17,49 +20,185 @@ impl HyprlandDesktop {
         HyprlandDesktop
     }
 
-    pub fn focus_workspace(&mut self, id: WorkspaceId) {
+    /// Shows or hides the special workspace on the focused monitor.
+    fn toggle_special_workspace(&mut self, id: WorkspaceId) {
+        if let Some(name) = special_workspace_name(id) {
+            let _ = dispatch!(ToggleSpecialWorkspace, name);
+        }
+    }
+}
+
+impl DesktopBackend for HyprlandDesktop {
+    fn focus_workspace(&mut self, id: WorkspaceId) {
+        if id < 0 {
+            self.toggle_special_workspace(id);
+            return;
+        }
         let _ = dispatch!(Workspace, WorkspaceIdentifierWithSpecial::Id(id as i32));
     }
 
-    pub fn cycle_workspace(&mut self, forward: bool) {
-        let _ = dispatch!(
-            Workspace,
-            WorkspaceIdentifierWithSpecial::RelativeMonitor(match forward {
-                true => 1,
-                false => -1,
-            })
-        );
+    fn move_window_to_workspace(&mut self, id: WorkspaceId) {
+        let workspace = if id < 0 {
+            let Some(name) = special_workspace_name(id) else {
+                return;
+            };
+            WorkspaceIdentifierWithSpecial::Special(name.as_deref())
+        } else {
+            WorkspaceIdentifierWithSpecial::Id(id as i32)
+        };
+        let _ = dispatch!(MoveToWorkspaceSilent, workspace, None);
     }
 
-    pub fn subscription(&self) -> iced::Subscription<Message> {
+    fn cycle_keyboard_layout(&mut self) {
+        let _ = switch_xkb_layout::call("all", SwitchXKBLayoutCmdTypes::Next);
+    }
+
+    fn subscription(&self) -> iced::Subscription<Message> {
         #[derive(Hash)]
         struct HyprlandEvents;
 
+        type Events = BoxStream<'static, Result<HyprEvent, HyprError>>;
+
         iced::Subscription::run_with_id(
             HyprlandEvents,
-            once(async { make_workspace_infos().await.map(Message::WorkspacesChanged) })
-                .filter_map(|e| async { e })
-                .chain(EventStream::new().filter_map(process_event)),
+            unfold(
+                (None::<Events>, HashSet::new(), Backoff::default()),
+                |(mut events, mut urgent, mut backoff)| async move {
+                    loop {
+                        let Some(stream) = &mut events else {
+                            // (Re)connect and emit the full state, as events may have been missed
+                            // while disconnected.
+                            match make_workspace_infos(&mut urgent).await {
+                                Some(workspace_infos) => {
+                                    events = Some(EventStream::new().boxed());
+                                    let messages = vec![
+                                        Message::WorkspacesChanged(workspace_infos),
+                                        Message::FocusedWindowChanged(active_window().await),
+                                        Message::KeyboardLayoutChanged(active_layout().await),
+                                        // Hyprland starts in the default submap
+                                        Message::SubmapChanged(None),
+                                    ];
+                                    return Some((messages, (events, urgent, backoff)));
+                                }
+                                None => {
+                                    backoff.wait().await;
+                                    continue;
+                                }
+                            }
+                        };
+
+                        match stream.next().await {
+                            Some(Ok(event)) => {
+                                backoff.reset();
+                                let messages = process_event(event, &mut urgent).await;
+                                if !messages.is_empty() {
+                                    return Some((messages, (events, urgent, backoff)));
+                                }
+                            }
+                            // The socket was closed, e.g. because Hyprland restarted
+                            Some(Err(HyprError::IoError(e))) => {
+                                eprintln!("Hyprland event stream disconnected: {e}");
+                                events = None;
+                                backoff.wait().await;
+                            }
+                            // Ignore events we fail to parse
+                            Some(Err(_)) => {}
+                            None => {
+                                eprintln!("Hyprland event stream closed");
+                                events = None;
+                                backoff.wait().await;
+                            }
+                        }
+                    }
+                },
+            )
+            .flat_map(stream::iter),
         )
     }
 }
 
-async fn process_event(event: Result<HyprEvent, HyprError>) -> Option<Message> {
-    let Ok(event) = event else {
-        return None;
-    };
+/// The name of a special workspace as expected by dispatchers, or `Some(None)` for the unnamed
+/// one.
+fn special_workspace_name(id: WorkspaceId) -> Option<Option<String>> {
+    let workspaces = Workspaces::get().ok()?;
+    let workspace = workspaces.into_iter().find(|w| w.id as WorkspaceId == id)?;
+    // Named special workspaces are called `special:<name>`, the unnamed one just `special`
+    Some(workspace.name.strip_prefix("special:").map(str::to_string))
+}
+
+/// `urgent` holds the workspaces with windows that requested attention since they were last
+/// visited.
+async fn process_event(event: HyprEvent, urgent: &mut HashSet<WorkspaceId>) -> Vec<Message> {
     use HyprEvent::*;
     match event {
-        MonitorAdded(_) | MonitorRemoved(_) | WorkspaceChanged(_) | WorkspaceDeleted(_)
-        | WorkspaceAdded(_) | WorkspaceMoved(_) | WindowOpened(_) | WindowClosed(_)
-        | WindowMoved(_) | FloatStateChanged(_) => {
-            make_workspace_infos().await.map(Message::WorkspacesChanged)
+        UrgentStateChanged(address) => {
+            let Ok(clients) = Clients::get_async().await else {
+                return Vec::new();
+            };
+            if let Some(client) = clients.into_iter().find(|c| c.address == address) {
+                urgent.insert(client.workspace.id as WorkspaceId);
+            }
+            make_workspace_infos(urgent)
+                .await
+                .map(Message::WorkspacesChanged)
+                .into_iter()
+                .collect()
         }
-        _ => None,
+        MonitorAdded(_)
+        | MonitorRemoved(_)
+        | WorkspaceChanged(_)
+        | WorkspaceDeleted(_)
+        | WorkspaceAdded(_)
+        | WorkspaceMoved(_)
+        | WindowOpened(_)
+        | WindowClosed(_)
+        | WindowMoved(_)
+        | FloatStateChanged(_)
+        | FullscreenStateChanged(_)
+        // `activespecial`: a special workspace was shown or hidden on a monitor
+        | ChangedSpecial(_)
+        | SpecialRemoved(_) => make_workspace_infos(urgent)
+            .await
+            .map(Message::WorkspacesChanged)
+            .into_iter()
+            .collect(),
+        // Also sent when the title of the active window changes
+        ActiveWindowChanged(window) => {
+            vec![Message::FocusedWindowChanged(window.map(|w| WindowInfo {
+                app_id: Some(w.class),
+                title: w.title,
+            }))]
+        }
+        SubMapChanged(submap) => {
+            vec![Message::SubmapChanged(
+                Some(submap).filter(|s| !s.is_empty()),
+            )]
+        }
+        LayoutChanged(layout) => vec![Message::KeyboardLayoutChanged(Some(layout.layout_name))],
+        _ => Vec::new(),
     }
 }
 
-async fn make_workspace_infos() -> Option<Vec<WorkspaceInfo>> {
+/// Active layout of the main keyboard.
+async fn active_layout() -> Option<String> {
+    let devices = Devices::get_async().await.ok()?;
+    let keyboard = devices
+        .keyboards
+        .iter()
+        .find(|k| k.main)
+        .or(devices.keyboards.first())?;
+    Some(keyboard.active_keymap.clone())
+}
+
+async fn active_window() -> Option<WindowInfo> {
+    let client = Client::get_active_async().await.ok()??;
+    Some(WindowInfo {
+        app_id: Some(client.class),
+        title: client.title,
+    })
+}
+
+async fn make_workspace_infos(urgent: &mut HashSet<WorkspaceId>) -> Option<Vec<WorkspaceInfo>> {
     let monitors = Monitors::get_async()
         .await
         .ok()?
@@ -88,6 +227,14 @@ async fn make_workspace_infos() -> Option<Vec<WorkspaceInfo>> {
         .into_iter()
         .collect::<Vec<_>>();
 
+    // Urgency is cleared once the workspace is visited
+    urgent.retain(|id| {
+        !monitors.iter().any(|m| {
+            m.active_workspace.id as WorkspaceId == *id
+                || m.special_workspace.id as WorkspaceId == *id
+        })
+    });
+
     let mut workspace_infos = all_workspaces
         .into_iter()
         .map(|(id, output)| {
@@ -96,26 +243,65 @@ async fn make_workspace_infos() -> Option<Vec<WorkspaceInfo>> {
                     output: Some(w.monitor.clone()),
                     id: w.id as WorkspaceId,
                     idx: w.id,
+                    name: Some(w.name.clone()),
+                    app_ids: app_ids(&clients, w.id),
                     is_active: monitors.iter().any(|m| m.active_workspace.id == w.id),
+                    is_urgent: urgent.contains(&(w.id as WorkspaceId)),
                     has_windows: w.windows > 0,
-                    transparent_bar: w.windows == 0
-                        || clients
-                            .iter()
-                            .filter(|c| c.workspace.id == w.id)
-                            .all(|c| c.floating),
+                    has_fullscreen: w.fullscreen,
+                    has_tiled_windows: clients
+                        .iter()
+                        .any(|c| c.workspace.id == w.id && !c.floating),
+                    windows_unknown: false,
+                    is_special: false,
                 }
             } else {
                 WorkspaceInfo {
                     output,
                     id,
                     idx: id as i32,
+                    name: None,
+                    app_ids: Vec::new(),
                     is_active: false,
+                    is_urgent: false,
                     has_windows: false,
-                    transparent_bar: false,
+                    has_fullscreen: false,
+                    has_tiled_windows: false,
+                    windows_unknown: false,
+                    is_special: false,
                 }
             }
         })
         .collect::<Vec<_>>();
+
+    // Special workspaces have negative ids and only exist while they have windows
+    workspace_infos.extend(workspaces.iter().filter(|w| w.id < 0).map(|w| {
+        WorkspaceInfo {
+            output: Some(w.monitor.clone()),
+            id: w.id as WorkspaceId,
+            // Show them after the regular workspaces
+            idx: i32::MAX,
+            name: w.name.strip_prefix("special:").map(str::to_string),
+            app_ids: app_ids(&clients, w.id),
+            is_active: monitors.iter().any(|m| m.special_workspace.id == w.id),
+            is_urgent: urgent.contains(&(w.id as WorkspaceId)),
+            has_windows: w.windows > 0,
+            has_fullscreen: w.fullscreen,
+            has_tiled_windows: clients
+                .iter()
+                .any(|c| c.workspace.id == w.id && !c.floating),
+            windows_unknown: false,
+            is_special: true,
+        }
+    }));
     workspace_infos.sort_by_key(|info| info.idx);
     Some(workspace_infos)
 }
+
+fn app_ids(clients: &[Client], workspace_id: i32) -> Vec<String> {
+    clients
+        .iter()
+        .filter(|c| c.workspace.id == workspace_id)
+        .map(|c| c.class.clone())
+        .collect()
+}
@@ -0,0 +1,192 @@
+//! Scripted backend replaying workspace states, for working on the bar without a compositor.
+//!
+//! The script is a JSON array of steps, each an array of `WorkspaceInfo` objects with camelCase
+//! keys. Steps are replayed in order, one every `interval`, and focus calls switch the active
+//! workspace of the current step.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use iced::futures::{Stream, StreamExt};
+use tokio::sync::watch;
+
+use super::{DesktopBackend, WorkspaceId, WorkspaceInfo};
+use crate::message::Message;
+
+#[cfg(feature = "mock")]
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(2);
+
+pub struct MockDesktop {
+    steps: Arc<[Vec<WorkspaceInfo>]>,
+    interval: Duration,
+    /// Workspaces currently shown, updated by the replay and by focus calls.
+    tx: watch::Sender<Vec<WorkspaceInfo>>,
+    /// Every workspace passed to `focus_workspace`, in order.
+    focus_calls: Arc<Mutex<Vec<WorkspaceId>>>,
+}
+
+impl MockDesktop {
+    pub fn new(steps: Vec<Vec<WorkspaceInfo>>, interval: Duration) -> Self {
+        Self {
+            steps: steps.into(),
+            interval,
+            tx: watch::Sender::new(Vec::new()),
+            focus_calls: Default::default(),
+        }
+    }
+
+    /// Reads the script at `LIMBO_MOCK_SCRIPT`, with the delay between steps in milliseconds
+    /// from `LIMBO_MOCK_INTERVAL`.
+    #[cfg(feature = "mock")]
+    pub fn from_env() -> Option<Self> {
+        let Some(path) = std::env::var_os("LIMBO_MOCK_SCRIPT") else {
+            eprintln!("LIMBO_MOCK_SCRIPT must be set to use the mock desktop");
+            return None;
+        };
+        let steps = std::fs::read(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|script| serde_json::from_slice(&script).map_err(|e| e.to_string()))
+            .inspect_err(|e| eprintln!("Failed to read mock script {}: {e}", path.display()))
+            .ok()?;
+        let interval = std::env::var("LIMBO_MOCK_INTERVAL")
+            .ok()
+            .and_then(|ms| ms.parse().ok())
+            .map_or(DEFAULT_INTERVAL, Duration::from_millis);
+        Some(Self::new(steps, interval))
+    }
+
+    /// Shared list of the workspaces passed to `focus_workspace`.
+    #[cfg(test)]
+    pub fn focus_calls(&self) -> Arc<Mutex<Vec<WorkspaceId>>> {
+        self.focus_calls.clone()
+    }
+
+    /// The workspaces after every step of the script and every focus call.
+    pub fn events(&self) -> impl Stream<Item = Vec<WorkspaceInfo>> + Send + 'static {
+        let steps = self.steps.clone();
+        let interval = self.interval;
+        let tx = self.tx.clone();
+        iced::futures::stream::unfold(
+            (tx.subscribe(), 0, tokio::time::Instant::now()),
+            move |(mut rx, next_step, deadline)| {
+                let steps = steps.clone();
+                let tx = tx.clone();
+                async move {
+                    let (next_step, deadline) = match steps.get(next_step) {
+                        Some(step) => tokio::select! {
+                            _ = tokio::time::sleep_until(deadline) => {
+                                tx.send_replace(step.clone());
+                                (next_step + 1, deadline + interval)
+                            }
+                            changed = rx.changed() => {
+                                changed.ok()?;
+                                (next_step, deadline)
+                            }
+                        },
+                        // The script is over, only focus calls change the workspaces
+                        None => {
+                            rx.changed().await.ok()?;
+                            (next_step, deadline)
+                        }
+                    };
+                    let workspace_infos = rx.borrow_and_update().clone();
+                    Some((workspace_infos, (rx, next_step, deadline)))
+                }
+            },
+        )
+    }
+}
+
+impl DesktopBackend for MockDesktop {
+    fn focus_workspace(&mut self, id: WorkspaceId) {
+        self.focus_calls.lock().unwrap().push(id);
+        self.tx.send_modify(|workspace_infos| {
+            let Some(output) = workspace_infos
+                .iter()
+                .find(|w| w.id == id)
+                .map(|w| w.output.clone())
+            else {
+                return;
+            };
+            for w in workspace_infos.iter_mut().filter(|w| w.output == output) {
+                w.is_active = w.id == id;
+            }
+        });
+    }
+
+    fn subscription(&self) -> iced::Subscription<Message> {
+        #[derive(Hash)]
+        struct MockEvents;
+
+        iced::Subscription::run_with_id(MockEvents, self.events().map(Message::WorkspacesChanged))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    /// A workspace of `output`, with tiled windows unless it's empty.
+    pub fn workspace(
+        output: &str,
+        id: WorkspaceId,
+        is_active: bool,
+        has_windows: bool,
+    ) -> WorkspaceInfo {
+        WorkspaceInfo {
+            output: Some(output.to_string()),
+            id,
+            idx: id as i32,
+            is_active,
+            has_windows,
+            has_tiled_windows: has_windows,
+            ..Default::default()
+        }
+    }
+
+    fn active_ids(workspace_infos: &[WorkspaceInfo]) -> Vec<WorkspaceId> {
+        workspace_infos
+            .iter()
+            .filter(|w| w.is_active)
+            .map(|w| w.id)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn replays_steps_in_order() {
+        let mock = MockDesktop::new(
+            vec![
+                vec![workspace("DP-1", 1, true, false)],
+                vec![
+                    workspace("DP-1", 1, false, false),
+                    workspace("DP-1", 2, true, true),
+                ],
+            ],
+            Duration::from_millis(1),
+        );
+        let mut events = Box::pin(mock.events());
+
+        assert_eq!(active_ids(&events.next().await.unwrap()), [1]);
+        assert_eq!(active_ids(&events.next().await.unwrap()), [2]);
+    }
+
+    #[tokio::test]
+    async fn focus_switches_the_active_workspace_of_its_output() {
+        let mut mock = MockDesktop::new(
+            vec![vec![
+                workspace("DP-1", 1, true, true),
+                workspace("DP-1", 2, false, false),
+                workspace("HDMI-A-1", 3, true, false),
+            ]],
+            Duration::ZERO,
+        );
+        let mut events = Box::pin(mock.events());
+        events.next().await.unwrap();
+
+        mock.focus_workspace(2);
+        assert_eq!(active_ids(&events.next().await.unwrap()), [2, 3]);
+        // Unknown workspaces are recorded but change nothing
+        mock.focus_workspace(9);
+        assert_eq!(*mock.focus_calls().lock().unwrap(), [2, 9]);
+    }
+}
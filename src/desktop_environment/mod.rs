@@ -1,73 +1,331 @@
+use std::sync::{Arc, Mutex};
+
+use serde::Deserialize;
+use serde::de::IntoDeserializer;
+use serde::de::value::{Error as ValueError, StrDeserializer};
+
+use crate::config::types::DesktopKind;
 use crate::message::Message;
 
+#[cfg(feature = "ext-workspace")]
+mod ext_workspace_desktop;
 #[cfg(feature = "hyprland")]
 mod hyprland_desktop;
+#[cfg(any(test, feature = "mock"))]
+pub mod mock_desktop;
 #[cfg(feature = "niri")]
 mod niri_desktop;
-
-#[cfg(not(any(feature = "hyprland", feature = "niri")))]
-compile_error!("No desktop environment selected");
+#[cfg(feature = "river")]
+mod river_desktop;
+#[cfg(feature = "sway")]
+mod sway_desktop;
 
 pub type WorkspaceId = i64;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 pub struct WorkspaceInfo {
     pub output: Option<String>,
     pub id: WorkspaceId,
     pub idx: i32,
+    pub name: Option<String>,
+    /// App ids (or X11 classes) of the windows on the workspace.
+    pub app_ids: Vec<String>,
     pub is_active: bool,
+    /// A window on the workspace requested attention.
+    pub is_urgent: bool,
     pub has_windows: bool,
-    pub transparent_bar: bool,
+    /// The workspace shows a fullscreen window.
+    pub has_fullscreen: bool,
+    /// The workspace has windows that aren't floating.
+    pub has_tiled_windows: bool,
+    /// The backend can't see windows, so `has_windows` and `has_tiled_windows` being false
+    /// doesn't mean that the workspace is empty.
+    pub windows_unknown: bool,
+    /// A scratchpad workspace shown over the regular ones, e.g. a Hyprland special workspace.
+    pub is_special: bool,
 }
 
-pub enum Desktop {
-    #[cfg(feature = "hyprland")]
-    Hyprland(hyprland_desktop::HyprlandDesktop),
-    #[cfg(feature = "niri")]
-    Niri(niri_desktop::NiriDesktop),
+/// The focused window.
+#[derive(Debug, Clone)]
+pub struct WindowInfo {
+    /// Wayland app id or X11 class.
+    pub app_id: Option<String>,
+    pub title: String,
+}
+
+/// A compositor integration providing workspace state and actions.
+/// Backends are connected on a blocking thread, see [`Desktop::detect`].
+pub trait DesktopBackend: Send {
+    fn focus_workspace(&mut self, id: WorkspaceId);
+
+    fn move_window_to_workspace(&mut self, _id: WorkspaceId) {}
+
+    fn toggle_overview(&mut self) {}
+
+    fn cycle_keyboard_layout(&mut self) {}
+
+    fn subscription(&self) -> iced::Subscription<Message>;
 }
+
+/// The connected backend, if any.
+#[derive(Default)]
+pub struct Desktop(Option<Box<dyn DesktopBackend>>);
+
 impl Desktop {
-    pub fn new() -> Self {
-        #[cfg(feature = "hyprland")]
-        {
-            use hyprland::shared::HyprData;
-            if hyprland::data::Version::get().is_ok() {
-                return Self::Hyprland(hyprland_desktop::HyprlandDesktop::new());
+    /// Connects to the `requested` backend, or to the first one that is available.
+    pub fn new(requested: Option<DesktopKind>) -> Self {
+        Self(connect(requested))
+    }
+
+    /// Connects like [`Desktop::new`] on a blocking thread, as connecting waits on the
+    /// compositor's replies.
+    pub async fn detect(requested: Option<DesktopKind>) -> Detected {
+        let desktop = tokio::task::spawn_blocking(move || Self::new(requested))
+            .await
+            .ok();
+        Detected(Arc::new(Mutex::new(desktop)))
+    }
+
+    /// The backend requested with `LIMBO_DESKTOP` or the `general.desktop` config key.
+    pub fn requested(config: Option<DesktopKind>) -> Option<DesktopKind> {
+        let requested = match std::env::var("LIMBO_DESKTOP") {
+            Ok(value) => {
+                let deserializer: StrDeserializer<'_, ValueError> =
+                    value.as_str().into_deserializer();
+                match DesktopKind::deserialize(deserializer) {
+                    Ok(kind) => Some(kind),
+                    Err(e) => {
+                        eprintln!("Ignoring LIMBO_DESKTOP: {e}");
+                        config
+                    }
+                }
             }
-        }
+            Err(_) => config,
+        };
 
-        #[cfg(feature = "niri")]
-        if let Ok(socket) = niri_ipc::socket::Socket::connect() {
-            return Self::Niri(niri_desktop::NiriDesktop::new(socket));
+        if requested.is_some_and(|kind| !is_supported(kind)) {
+            eprintln!("limbo was built without support for the {requested:?} desktop");
         }
+        requested
+    }
+
+    /// Whether detection should be retried, i.e. no backend is connected yet and one may still
+    /// show up. A backend that wasn't built in never will, which [`Desktop::requested`] reports.
+    pub fn should_retry(&self, requested: Option<DesktopKind>) -> bool {
+        self.0.is_none()
+            && requested != Some(DesktopKind::None)
+            && requested.is_none_or(is_supported)
+    }
 
-        unreachable!()
+    pub fn is_connected(&self) -> bool {
+        self.0.is_some()
     }
 
     pub fn focus_workspace(&mut self, id: WorkspaceId) {
-        match self {
-            #[cfg(feature = "hyprland")]
-            Desktop::Hyprland(hyprland_desktop) => hyprland_desktop.focus_workspace(id),
-            #[cfg(feature = "niri")]
-            Desktop::Niri(niri_desktop) => niri_desktop.focus_workspace(id),
+        if let Some(backend) = &mut self.0 {
+            backend.focus_workspace(id);
+        }
+    }
+
+    /// Focuses the regular workspace after or before the active one on `output`, wrapping
+    /// around at the ends if `wrap` is set.
+    pub fn cycle_workspace(
+        &mut self,
+        workspace_infos: &[WorkspaceInfo],
+        output: &str,
+        forward: bool,
+        wrap: bool,
+    ) {
+        let workspaces = workspace_infos
+            .iter()
+            .filter(|w| w.output.as_deref() == Some(output) && !w.is_special)
+            .collect::<Vec<_>>();
+        let Some(pos) = workspaces.iter().position(|w| w.is_active) else {
+            return;
+        };
+        let len = workspaces.len();
+        let next = match (forward, wrap) {
+            (true, _) if pos + 1 < len => pos + 1,
+            (true, true) => 0,
+            (false, _) if pos > 0 => pos - 1,
+            (false, true) => len - 1,
+            _ => return,
+        };
+        if next != pos {
+            self.focus_workspace(workspaces[next].id);
+        }
+    }
+
+    pub fn move_window_to_workspace(&mut self, id: WorkspaceId) {
+        if let Some(backend) = &mut self.0 {
+            backend.move_window_to_workspace(id);
+        }
+    }
+
+    pub fn toggle_overview(&mut self) {
+        if let Some(backend) = &mut self.0 {
+            backend.toggle_overview();
         }
     }
 
-    pub fn cycle_workspace(&mut self, forward: bool) {
-        match self {
-            #[cfg(feature = "hyprland")]
-            Desktop::Hyprland(hyprland_desktop) => hyprland_desktop.cycle_workspace(forward),
-            #[cfg(feature = "niri")]
-            Desktop::Niri(niri_desktop) => niri_desktop.cycle_workspace(forward),
+    pub fn cycle_keyboard_layout(&mut self) {
+        if let Some(backend) = &mut self.0 {
+            backend.cycle_keyboard_layout();
         }
     }
 
     pub fn subscription(&self) -> iced::Subscription<Message> {
-        match self {
-            #[cfg(feature = "hyprland")]
-            Desktop::Hyprland(hyprland_desktop) => hyprland_desktop.subscription(),
-            #[cfg(feature = "niri")]
-            Desktop::Niri(niri_desktop) => niri_desktop.subscription(),
+        match &self.0 {
+            Some(backend) => backend.subscription(),
+            None => iced::Subscription::none(),
+        }
+    }
+}
+
+/// The result of [`Desktop::detect`], shared so that it can be sent in a [`Message`].
+#[derive(Clone)]
+pub struct Detected(Arc<Mutex<Option<Desktop>>>);
+
+impl Detected {
+    /// The detected desktop, which only the first call gets.
+    pub fn take(&self) -> Option<Desktop> {
+        self.0.lock().unwrap().take()
+    }
+}
+
+impl std::fmt::Debug for Detected {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Detected")
+    }
+}
+
+fn connect(requested: Option<DesktopKind>) -> Option<Box<dyn DesktopBackend>> {
+    let wants = |kind| requested.is_none_or(|requested| requested == kind);
+
+    // Only used when explicitly requested
+    #[cfg(feature = "mock")]
+    if requested == Some(DesktopKind::Mock) {
+        return mock_desktop::MockDesktop::from_env()
+            .map(|mock_desktop| Box::new(mock_desktop) as Box<dyn DesktopBackend>);
+    }
+
+    #[cfg(feature = "hyprland")]
+    if wants(DesktopKind::Hyprland) {
+        use hyprland::shared::HyprData;
+        if hyprland::data::Version::get().is_ok() {
+            return Some(Box::new(hyprland_desktop::HyprlandDesktop::new()));
+        }
+    }
+
+    #[cfg(feature = "niri")]
+    if wants(DesktopKind::Niri)
+        && let Some(niri_desktop) = niri_desktop::NiriDesktop::connect()
+    {
+        return Some(Box::new(niri_desktop));
+    }
+
+    #[cfg(feature = "sway")]
+    if wants(DesktopKind::Sway)
+        && let Some(sway_desktop) = sway_desktop::SwayDesktop::connect()
+    {
+        return Some(Box::new(sway_desktop));
+    }
+
+    #[cfg(feature = "river")]
+    if wants(DesktopKind::River)
+        && let Some(river_desktop) = river_desktop::RiverDesktop::connect()
+    {
+        return Some(Box::new(river_desktop));
+    }
+
+    // Fall back to the standard protocol when no compositor-specific IPC is available
+    #[cfg(feature = "ext-workspace")]
+    if wants(DesktopKind::ExtWorkspace)
+        && let Some(ext_workspace_desktop) = ext_workspace_desktop::ExtWorkspaceDesktop::connect()
+    {
+        return Some(Box::new(ext_workspace_desktop));
+    }
+
+    None
+}
+
+fn is_supported(kind: DesktopKind) -> bool {
+    match kind {
+        DesktopKind::Hyprland => cfg!(feature = "hyprland"),
+        DesktopKind::Niri => cfg!(feature = "niri"),
+        DesktopKind::Sway => cfg!(feature = "sway"),
+        DesktopKind::River => cfg!(feature = "river"),
+        DesktopKind::ExtWorkspace => cfg!(feature = "ext-workspace"),
+        DesktopKind::Mock => cfg!(feature = "mock"),
+        DesktopKind::None => true,
+    }
+}
+
+/// Exponential backoff between attempts to reconnect to a compositor's event stream.
+#[cfg(any(feature = "hyprland", feature = "niri", feature = "sway"))]
+struct Backoff {
+    delay: std::time::Duration,
+}
+
+#[cfg(any(feature = "hyprland", feature = "niri", feature = "sway"))]
+impl Backoff {
+    const MIN_DELAY: std::time::Duration = std::time::Duration::from_millis(250);
+    const MAX_DELAY: std::time::Duration = std::time::Duration::from_secs(30);
+
+    async fn wait(&mut self) {
+        tokio::time::sleep(self.next_delay()).await;
+    }
+
+    /// The delay before the next attempt, doubling the one after it.
+    fn next_delay(&mut self) -> std::time::Duration {
+        let delay = self.delay;
+        self.delay = (delay * 2).min(Self::MAX_DELAY);
+        delay
+    }
+
+    fn reset(&mut self) {
+        self.delay = Self::MIN_DELAY;
+    }
+}
+
+#[cfg(any(feature = "hyprland", feature = "niri", feature = "sway"))]
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            delay: Self::MIN_DELAY,
         }
     }
 }
+
+#[cfg(all(test, any(feature = "hyprland", feature = "niri", feature = "sway")))]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_its_cap() {
+        let mut backoff = Backoff::default();
+        let delays = (0..10).map(|_| backoff.next_delay()).collect::<Vec<_>>();
+        let ms = Duration::from_millis;
+        assert_eq!(
+            delays,
+            [
+                ms(250),
+                ms(500),
+                ms(1000),
+                ms(2000),
+                ms(4000),
+                ms(8000),
+                ms(16000),
+                ms(30000),
+                ms(30000),
+                ms(30000),
+            ]
+        );
+
+        backoff.reset();
+        assert_eq!(backoff.next_delay(), ms(250));
+    }
+}
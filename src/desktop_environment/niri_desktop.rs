@@ -1,114 +1,245 @@
-use iced::futures::StreamExt;
-use iced::futures::stream::{once, unfold};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use iced::futures::stream::{self, unfold};
+use iced::futures::{Stream, StreamExt};
 use niri_ipc::socket::Socket;
 use niri_ipc::state::{EventStreamState, EventStreamStatePart};
 use niri_ipc::{Action, Request, Response};
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::net::{UnixSocket, UnixStream};
 
-use super::{WorkspaceId, WorkspaceInfo};
+use super::{Backoff, DesktopBackend, WindowInfo, WorkspaceId, WorkspaceInfo};
 use crate::message::Message;
 
 pub struct NiriDesktop {
+    socket_path: PathBuf,
     socket: Socket,
 }
 impl NiriDesktop {
-    pub fn new(socket: Socket) -> Self {
-        Self { socket }
+    /// Connects to the socket at `$NIRI_SOCKET`.
+    pub fn connect() -> Option<Self> {
+        let socket_path = std::env::var_os(niri_ipc::socket::SOCKET_PATH_ENV)?;
+        Self::new(socket_path.into()).ok()
+    }
+
+    pub fn new(socket_path: PathBuf) -> std::io::Result<Self> {
+        let socket = Socket::connect_to(&socket_path)?;
+        Ok(Self {
+            socket_path,
+            socket,
+        })
+    }
+
+    /// Messages for the events of niri's event stream, reconnecting with backoff whenever it
+    /// closes.
+    fn events(&self) -> impl Stream<Item = Message> + Send + 'static {
+        unfold(
+            (
+                self.socket_path.clone(),
+                None,
+                String::new(),
+                EventStreamState::default(),
+                OutputSizes::new(),
+                Backoff::default(),
+            ),
+            |(path, mut socket, mut buf, mut state, mut outputs, mut backoff)| async move {
+                loop {
+                    let Some(stream) = &mut socket else {
+                        match new_event_stream(&path).await {
+                            // niri sends the full state when the event stream starts, so the
+                            // first relevant event re-emits every workspace.
+                            Some(stream) => {
+                                socket = Some(stream);
+                                state = EventStreamState::default();
+                                outputs = output_sizes(&path).await.unwrap_or_default();
+                            }
+                            None => backoff.wait().await,
+                        }
+                        continue;
+                    };
+
+                    // Ignore events we fail to parse.
+                    // In particular, ignore Event::WindowFocusTimestampChanged, which we
+                    // do not know how to deserialize since it hasn't been released yet.
+                    let event = match read_event(&mut buf, stream).await {
+                        Ok(Some(event)) => event,
+                        Ok(None) => continue,
+                        Err(e) => {
+                            eprintln!("niri event stream disconnected: {e}");
+                            socket = None;
+                            backoff.wait().await;
+                            continue;
+                        }
+                    };
+                    backoff.reset();
+                    state.apply(event.clone());
+                    use niri_ipc::Event::*;
+
+                    // niri has no event for outputs, but workspaces move along with them
+                    if let WorkspacesChanged { .. } = event
+                        && outputs_changed(&state, &outputs)
+                    {
+                        outputs = output_sizes(&path).await.unwrap_or_default();
+                    }
+
+                    // Only emit messages on relevant events.
+                    let messages = match event {
+                        WorkspacesChanged { .. }
+                        | WorkspaceActivated { .. }
+                        | WorkspaceActiveWindowChanged { .. }
+                        | WindowsChanged { .. }
+                        | WindowOpenedOrChanged { .. }
+                        | WindowFocusChanged { .. }
+                        | WindowClosed { .. }
+                        | WindowUrgencyChanged { .. }
+                        | WindowLayoutsChanged { .. }
+                        | OverviewOpenedOrClosed { .. } => vec![
+                            Message::WorkspacesChanged(make_workspace_infos(&state, &outputs)),
+                            Message::FocusedWindowChanged(focused_window(&state)),
+                        ],
+                        KeyboardLayoutsChanged { .. } | KeyboardLayoutSwitched { .. } => {
+                            vec![Message::KeyboardLayoutChanged(keyboard_layout(&state))]
+                        }
+                        _ => continue,
+                    };
+                    return Some((messages, (path, socket, buf, state, outputs, backoff)));
+                }
+            },
+        )
+        .flat_map(stream::iter)
     }
 
-    pub fn focus_workspace(&mut self, id: WorkspaceId) {
-        let _ = self.socket.send(Request::Action(Action::FocusWorkspace {
+    /// Sends `action`, reconnecting once if the socket was closed by a niri restart.
+    fn send_action(&mut self, action: Action) {
+        if self.socket.send(Request::Action(action.clone())).is_ok() {
+            return;
+        }
+        if let Ok(socket) = Socket::connect_to(&self.socket_path) {
+            self.socket = socket;
+            let _ = self.socket.send(Request::Action(action));
+        }
+    }
+}
+
+impl DesktopBackend for NiriDesktop {
+    fn focus_workspace(&mut self, id: WorkspaceId) {
+        self.send_action(Action::FocusWorkspace {
             reference: niri_ipc::WorkspaceReferenceArg::Id(id as u64),
-        }));
+        });
     }
 
-    pub fn cycle_workspace(&mut self, forward: bool) {
-        let action = if forward {
-            Action::FocusWorkspaceUp {}
-        } else {
-            Action::FocusWorkspaceDown {}
-        };
-        let _ = self.socket.send(Request::Action(action));
+    fn move_window_to_workspace(&mut self, id: WorkspaceId) {
+        self.send_action(Action::MoveWindowToWorkspace {
+            window_id: None,
+            reference: niri_ipc::WorkspaceReferenceArg::Id(id as u64),
+            focus: false,
+        });
+    }
+
+    fn toggle_overview(&mut self) {
+        self.send_action(Action::ToggleOverview {});
     }
 
-    pub fn subscription(&self) -> iced::Subscription<Message> {
+    fn cycle_keyboard_layout(&mut self) {
+        self.send_action(Action::SwitchLayout {
+            layout: niri_ipc::LayoutSwitchTarget::Next,
+        });
+    }
+
+    fn subscription(&self) -> iced::Subscription<Message> {
         #[derive(Hash)]
         struct NiriEvents;
 
-        iced::Subscription::run_with_id(
-            NiriEvents,
-            once(new_event_stream())
-                .filter_map(|e| async { e })
-                .flat_map(|socket| {
-                    unfold(
-                        (socket, String::new(), EventStreamState::default()),
-                        |(mut socket, mut buf, mut state)| async {
-                            loop {
-                                // Ignore errors.
-                                // In particular, ignore Event::WindowFocusTimestampChanged, which we
-                                // do not know how to deserialize since it hasn't been released yet.
-                                if let Some(event) = read_event(&mut buf, &mut socket).await {
-                                    state.apply(event.clone());
-                                    use niri_ipc::Event::*;
-
-                                    // Only emit messages on relevant events.
-                                    if let WorkspacesChanged { .. }
-                                    | WorkspaceActivated { .. }
-                                    | WorkspaceActiveWindowChanged { .. }
-                                    | WindowOpenedOrChanged { .. }
-                                    | WindowFocusChanged { .. }
-                                    | WindowClosed { .. }
-                                    | OverviewOpenedOrClosed { .. } = event
-                                    {
-                                        break;
-                                    }
-                                };
-                            }
-
-                            Some((
-                                Message::WorkspacesChanged(make_workspace_infos(&state)),
-                                (socket, buf, state),
-                            ))
-                        },
-                    )
-                }),
-        )
+        iced::Subscription::run_with_id(NiriEvents, self.events())
     }
 }
 
-async fn new_event_stream() -> Option<BufReader<UnixStream>> {
+/// Logical sizes of the outputs by name.
+type OutputSizes = HashMap<String, (f64, f64)>;
+
+/// Sends `request` on a new connection, returning the connection along with the response.
+async fn request(
+    socket_path: &Path,
+    request: &Request,
+) -> Option<(BufReader<UnixStream>, Response)> {
     let socket = UnixSocket::new_stream()
         .ok()?
-        .connect(std::env::var(niri_ipc::socket::SOCKET_PATH_ENV).ok()?)
+        .connect(socket_path)
         .await
         .ok()?;
     let mut socket = BufReader::new(socket);
 
-    let mut buf = serde_json::to_string(&Request::EventStream).ok()?;
+    let mut buf = serde_json::to_string(request).ok()?;
     buf.push('\n');
     socket.write_all(buf.as_bytes()).await.ok()?;
     buf.clear();
 
     socket.read_line(&mut buf).await.ok()?;
     let reply: niri_ipc::Reply = serde_json::from_str(&buf).ok()?;
-    if let Ok(Response::Handled) = reply {
-        Some(socket)
-    } else {
-        None
+    Some((socket, reply.ok()?))
+}
+
+async fn new_event_stream(socket_path: &Path) -> Option<BufReader<UnixStream>> {
+    match request(socket_path, &Request::EventStream).await? {
+        (socket, Response::Handled) => Some(socket),
+        _ => None,
     }
 }
 
+async fn output_sizes(socket_path: &Path) -> Option<OutputSizes> {
+    let Response::Outputs(outputs) = request(socket_path, &Request::Outputs).await?.1 else {
+        return None;
+    };
+    Some(
+        outputs
+            .into_iter()
+            .filter_map(|(name, output)| {
+                let logical = output.logical?;
+                Some((name, (logical.width as f64, logical.height as f64)))
+            })
+            .collect(),
+    )
+}
+
+/// Whether the outputs with workspaces differ from the ones `outputs` has sizes for.
+fn outputs_changed(state: &EventStreamState, outputs: &OutputSizes) -> bool {
+    let names = state
+        .workspaces
+        .workspaces
+        .values()
+        .filter_map(|w| w.output.as_ref())
+        .collect::<HashSet<_>>();
+    names.len() != outputs.len() || names.iter().any(|name| !outputs.contains_key(*name))
+}
+
+/// Reads the next event, returning `Ok(None)` if it can't be parsed and an error once the socket
+/// is closed.
 async fn read_event(
     buf: &mut String,
     socket: &mut BufReader<UnixStream>,
-) -> Option<niri_ipc::Event> {
+) -> std::io::Result<Option<niri_ipc::Event>> {
     buf.clear();
-    socket.read_line(buf).await.ok()?;
-    serde_json::from_str(buf).ok()
+    if socket.read_line(buf).await? == 0 {
+        return Err(std::io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(serde_json::from_str(buf).ok())
+}
+
+fn focused_window(state: &EventStreamState) -> Option<WindowInfo> {
+    let window = state.windows.windows.values().find(|win| win.is_focused)?;
+    Some(WindowInfo {
+        app_id: window.app_id.clone(),
+        title: window.title.clone().unwrap_or_default(),
+    })
+}
+
+fn keyboard_layout(state: &EventStreamState) -> Option<String> {
+    let layouts = state.keyboard_layouts.keyboard_layouts.as_ref()?;
+    layouts.names.get(layouts.current_idx as usize).cloned()
 }
 
-fn make_workspace_infos(state: &EventStreamState) -> Vec<WorkspaceInfo> {
+fn make_workspace_infos(state: &EventStreamState, outputs: &OutputSizes) -> Vec<WorkspaceInfo> {
     let mut workspace_infos = state
         .workspaces
         .workspaces
@@ -123,19 +254,130 @@ fn make_workspace_infos(state: &EventStreamState) -> Vec<WorkspaceInfo> {
                 output: w.output.clone(),
                 id: w.id as WorkspaceId,
                 idx: w.idx as i32,
+                name: w.name.clone(),
+                app_ids: state
+                    .windows
+                    .windows
+                    .values()
+                    .filter(|win| win.workspace_id == Some(w.id))
+                    .filter_map(|win| win.app_id.clone())
+                    .collect(),
                 is_active: w.is_active,
+                is_urgent: state
+                    .windows
+                    .windows
+                    .values()
+                    .any(|win| win.workspace_id == Some(w.id) && win.is_urgent),
                 has_windows,
-                transparent_bar: !has_windows
-                    || state.overview.is_open
-                    || state
+                has_fullscreen: has_fullscreen(state, w, outputs),
+                // Windows are shrunk into the overview, so none count as tiled while it's open
+                has_tiled_windows: !state.overview.is_open
+                    && state
                         .windows
                         .windows
                         .values()
-                        .filter(|win| win.workspace_id == Some(w.id))
-                        .all(|win| win.is_floating),
+                        .any(|win| win.workspace_id == Some(w.id) && !win.is_floating),
+                windows_unknown: false,
+                is_special: false,
             }
         })
         .collect::<Vec<_>>();
     workspace_infos.sort_by_key(|info| info.idx);
     workspace_infos
 }
+
+/// niri doesn't report fullscreen windows, so look for an active window covering its whole
+/// output instead.
+fn has_fullscreen(
+    state: &EventStreamState,
+    workspace: &niri_ipc::Workspace,
+    outputs: &OutputSizes,
+) -> bool {
+    let Some((width, height)) = workspace.output.as_ref().and_then(|o| outputs.get(o)) else {
+        return false;
+    };
+    workspace
+        .active_window_id
+        .and_then(|id| state.windows.windows.get(&id))
+        .is_some_and(|win| {
+            let (tile_width, tile_height) = win.layout.tile_size;
+            (tile_width - width).abs() < 1. && (tile_height - height).abs() < 1.
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use tokio::net::UnixListener;
+
+    use super::*;
+
+    const WORKSPACES_CHANGED: &str = r#"{"WorkspacesChanged":{"workspaces":[{"id":1,"idx":1,"name":null,"output":"DP-1","is_urgent":false,"is_active":true,"is_focused":true,"active_window_id":null}]}}"#;
+
+    /// Serves niri's IPC, closing the first event stream right after sending the workspaces.
+    async fn serve(listener: UnixListener) {
+        let event_streams = Arc::new(AtomicUsize::new(0));
+        while let Ok((socket, _)) = listener.accept().await {
+            let event_streams = event_streams.clone();
+            tokio::spawn(async move {
+                let mut socket = BufReader::new(socket);
+                let mut request = String::new();
+                socket.read_line(&mut request).await.ok()?;
+                if request.contains("EventStream") {
+                    let reply = format!("{{\"Ok\":\"Handled\"}}\n{WORKSPACES_CHANGED}\n");
+                    socket.write_all(reply.as_bytes()).await.ok()?;
+                    if event_streams.fetch_add(1, Ordering::SeqCst) > 0 {
+                        std::future::pending::<()>().await;
+                    }
+                } else if request.contains("Outputs") {
+                    socket
+                        .write_all(b"{\"Ok\":{\"Outputs\":{}}}\n")
+                        .await
+                        .ok()?;
+                }
+                Some(())
+            });
+        }
+    }
+
+    #[test]
+    fn output_sizes_are_read_again_when_outputs_change() {
+        let mut state = EventStreamState::default();
+        state.apply(serde_json::from_str(WORKSPACES_CHANGED).unwrap());
+
+        let mut outputs = OutputSizes::new();
+        assert!(outputs_changed(&state, &outputs));
+        outputs.insert("DP-1".to_string(), (1920., 1080.));
+        assert!(!outputs_changed(&state, &outputs));
+        outputs.insert("HDMI-A-1".to_string(), (1920., 1080.));
+        assert!(outputs_changed(&state, &outputs));
+    }
+
+    #[tokio::test]
+    async fn reconnects_after_the_event_stream_closes() {
+        let path = std::env::temp_dir().join(format!("limbo-niri-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        tokio::spawn(serve(UnixListener::bind(&path).unwrap()));
+
+        let desktop = NiriDesktop::new(path.clone()).unwrap();
+        let mut events = Box::pin(desktop.events());
+        for _ in 0..2 {
+            let workspace_infos = tokio::time::timeout(Duration::from_secs(5), async {
+                loop {
+                    if let Some(Message::WorkspacesChanged(workspace_infos)) = events.next().await {
+                        return workspace_infos;
+                    }
+                }
+            })
+            .await
+            .expect("no workspaces after reconnecting");
+            let ids = workspace_infos.iter().map(|w| w.id).collect::<Vec<_>>();
+            assert_eq!(ids, [1]);
+        }
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
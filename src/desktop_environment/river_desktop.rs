@@ -0,0 +1,462 @@
+//! River backend using the `river-status` and `river-control` Wayland protocols.
+//!
+//! River has tags rather than workspaces; tags 1-9 of every output are shown as workspaces, and
+//! several of them can be active at once.
+
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::watch;
+use wayland_client::backend::ObjectId;
+use wayland_client::globals::{GlobalListContents, registry_queue_init};
+use wayland_client::protocol::wl_output::{self, WlOutput};
+use wayland_client::protocol::wl_registry::{self, WlRegistry};
+use wayland_client::protocol::wl_seat::WlSeat;
+use wayland_client::{Connection, Dispatch, Proxy, QueueHandle, delegate_noop};
+
+use self::protocols::control::zriver_command_callback_v1::{self, ZriverCommandCallbackV1};
+use self::protocols::control::zriver_control_v1::ZriverControlV1;
+use self::protocols::status::zriver_output_status_v1::{self, ZriverOutputStatusV1};
+use self::protocols::status::zriver_seat_status_v1::{self, ZriverSeatStatusV1};
+use self::protocols::status::zriver_status_manager_v1::ZriverStatusManagerV1;
+use super::{DesktopBackend, WorkspaceId, WorkspaceInfo};
+use crate::message::Message;
+
+#[allow(
+    dead_code,
+    non_camel_case_types,
+    non_upper_case_globals,
+    unused_imports
+)]
+#[allow(clippy::all)]
+mod protocols {
+    pub mod status {
+        use wayland_client;
+        use wayland_client::protocol::*;
+
+        pub mod __interfaces {
+            use wayland_client::protocol::__interfaces::*;
+            wayland_scanner::generate_interfaces!("protocols/river-status-unstable-v1.xml");
+        }
+        use self::__interfaces::*;
+
+        wayland_scanner::generate_client_code!("protocols/river-status-unstable-v1.xml");
+    }
+
+    pub mod control {
+        use wayland_client;
+        use wayland_client::protocol::*;
+
+        pub mod __interfaces {
+            use wayland_client::protocol::__interfaces::*;
+            wayland_scanner::generate_interfaces!("protocols/river-control-unstable-v1.xml");
+        }
+        use self::__interfaces::*;
+
+        wayland_scanner::generate_client_code!("protocols/river-control-unstable-v1.xml");
+    }
+}
+
+const TAG_COUNT: u32 = 9;
+
+pub struct RiverDesktop {
+    connection: Connection,
+    qh: QueueHandle<State>,
+    control: ZriverControlV1,
+    seat: WlSeat,
+    /// Tags by workspace id, along with the name of their output.
+    tags: Arc<Mutex<Vec<(WorkspaceId, String, u32)>>>,
+    /// Name of the output focused by the seat.
+    focused_output: Arc<Mutex<Option<String>>>,
+    rx: Arc<tokio::sync::Mutex<watch::Receiver<Vec<WorkspaceInfo>>>>,
+}
+
+impl RiverDesktop {
+    /// Connects to the Wayland display, returning `None` if the compositor is not river.
+    /// Events are dispatched on a separate thread.
+    pub fn connect() -> Option<Self> {
+        let connection = Connection::connect_to_env().ok()?;
+        let (globals, mut queue) = registry_queue_init::<State>(&connection).ok()?;
+        let qh = queue.handle();
+        let status_manager = globals
+            .bind::<ZriverStatusManagerV1, _, _>(&qh, 1..=2, ())
+            .ok()?;
+        let control = globals.bind::<ZriverControlV1, _, _>(&qh, 1..=1, ()).ok()?;
+        let seat = globals.bind::<WlSeat, _, _>(&qh, 1..=1, ()).ok()?;
+        status_manager.get_river_seat_status(&seat, &qh, ());
+
+        let (tx, rx) = watch::channel(Vec::new());
+        let tags = Arc::new(Mutex::new(Vec::new()));
+        let focused_output_name = Arc::new(Mutex::new(None));
+        let mut state = State {
+            status_manager,
+            outputs: Vec::new(),
+            focused_output: None,
+            tags: tags.clone(),
+            focused_output_name: focused_output_name.clone(),
+            tx,
+        };
+        globals.contents().with_list(|list| {
+            for global in list {
+                if global.interface == WlOutput::interface().name {
+                    state.add_output(globals.registry(), global.name, global.version, &qh);
+                }
+            }
+        });
+
+        std::thread::spawn(move || {
+            while queue.blocking_dispatch(&mut state).is_ok() {}
+            eprintln!("river event queue closed");
+        });
+
+        Some(Self {
+            connection,
+            qh,
+            control,
+            seat,
+            tags,
+            focused_output: focused_output_name,
+            rx: Arc::new(tokio::sync::Mutex::new(rx)),
+        })
+    }
+
+    /// The output name and tag of a workspace.
+    fn tag(&self, id: WorkspaceId) -> Option<(String, u32)> {
+        self.tags
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(tag_id, _, _)| *tag_id == id)
+            .map(|(_, output, tag)| (output.clone(), *tag))
+    }
+
+    fn run_command(&self, args: &[&str]) {
+        for arg in args {
+            self.control.add_argument(arg.to_string());
+        }
+        self.control.run_command(&self.seat, &self.qh, ());
+        let _ = self.connection.flush();
+    }
+}
+
+impl DesktopBackend for RiverDesktop {
+    fn focus_workspace(&mut self, id: WorkspaceId) {
+        let Some((output, tag)) = self.tag(id) else {
+            return;
+        };
+        // `set-focused-tags` applies to the focused output
+        self.run_command(&["focus-output", &output]);
+        self.run_command(&["set-focused-tags", &(1 << tag).to_string()]);
+    }
+
+    /// Moves the focused view to the tag. Views can only be retagged on the focused output, as
+    /// sending them to another output moves the focus away from them.
+    fn move_window_to_workspace(&mut self, id: WorkspaceId) {
+        let Some((output, tag)) = self.tag(id) else {
+            return;
+        };
+        if self.focused_output.lock().unwrap().as_ref() == Some(&output) {
+            self.run_command(&["set-view-tags", &(1 << tag).to_string()]);
+        }
+    }
+
+    fn subscription(&self) -> iced::Subscription<Message> {
+        #[derive(Hash)]
+        struct RiverEvents;
+
+        iced::Subscription::run_with_id(
+            RiverEvents,
+            iced::futures::stream::unfold(self.rx.clone(), |rx| async move {
+                let value = {
+                    let mut rx = rx.lock().await;
+                    if rx.changed().await.is_ok() {
+                        Some(rx.borrow().clone())
+                    } else {
+                        None
+                    }
+                };
+                value.map(|v| (Message::WorkspacesChanged(v), rx))
+            }),
+        )
+    }
+}
+
+struct Output {
+    /// Name of the `wl_output` global, used to notice its removal.
+    global_name: u32,
+    output: WlOutput,
+    status: ZriverOutputStatusV1,
+    name: Option<String>,
+    focused_tags: u32,
+    /// Tags with at least one view.
+    occupied_tags: u32,
+    /// Tags with at least one urgent view, only sent from version 2.
+    urgent_tags: u32,
+}
+
+struct State {
+    status_manager: ZriverStatusManagerV1,
+    outputs: Vec<Output>,
+    focused_output: Option<ObjectId>,
+    tags: Arc<Mutex<Vec<(WorkspaceId, String, u32)>>>,
+    focused_output_name: Arc<Mutex<Option<String>>>,
+    tx: watch::Sender<Vec<WorkspaceInfo>>,
+}
+
+impl State {
+    fn add_output(
+        &mut self,
+        registry: &WlRegistry,
+        name: u32,
+        version: u32,
+        qh: &QueueHandle<Self>,
+    ) {
+        // `wl_output.name` was added in version 4
+        if version < 4 {
+            return;
+        }
+        let output = registry.bind::<WlOutput, _, _>(name, 4, qh, ());
+        let status = self.status_manager.get_river_output_status(&output, qh, ());
+        self.outputs.push(Output {
+            global_name: name,
+            output,
+            status,
+            name: None,
+            focused_tags: 0,
+            occupied_tags: 0,
+            urgent_tags: 0,
+        });
+    }
+
+    fn output_mut(&mut self, id: &ObjectId) -> Option<&mut Output> {
+        self.outputs.iter_mut().find(|o| o.output.id() == *id)
+    }
+
+    fn publish(&self) {
+        let mut tags = Vec::new();
+        let mut workspace_infos = Vec::new();
+        for output in &self.outputs {
+            let Some(name) = &output.name else {
+                continue;
+            };
+            // Keep ids unique across outputs
+            let base = (output.output.id().protocol_id() as WorkspaceId) << 5;
+            for tag in 0..TAG_COUNT {
+                let mask = 1 << tag;
+                let id = base | tag as WorkspaceId;
+                let has_windows = output.occupied_tags & mask != 0;
+                workspace_infos.push(WorkspaceInfo {
+                    output: Some(name.clone()),
+                    id,
+                    // Tags are numbered from 1, like the workspaces of other backends
+                    idx: tag as i32 + 1,
+                    name: None,
+                    app_ids: Vec::new(),
+                    is_active: output.focused_tags & mask != 0,
+                    is_urgent: output.urgent_tags & mask != 0,
+                    has_windows,
+                    // Not exposed by river-status
+                    has_fullscreen: false,
+                    // river-status doesn't tell floating views apart
+                    has_tiled_windows: has_windows,
+                    windows_unknown: false,
+                    is_special: false,
+                });
+                tags.push((id, name.clone(), tag));
+            }
+        }
+
+        *self.tags.lock().unwrap() = tags;
+        *self.focused_output_name.lock().unwrap() = self
+            .focused_output
+            .as_ref()
+            .and_then(|id| self.outputs.iter().find(|o| o.output.id() == *id))
+            .and_then(|o| o.name.clone());
+        let _ = self.tx.send(workspace_infos);
+    }
+}
+
+impl Dispatch<WlRegistry, GlobalListContents> for State {
+    fn event(
+        state: &mut Self,
+        registry: &WlRegistry,
+        event: wl_registry::Event,
+        _: &GlobalListContents,
+        _: &Connection,
+        qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            wl_registry::Event::Global {
+                name,
+                interface,
+                version,
+            } if interface == WlOutput::interface().name => {
+                state.add_output(registry, name, version, qh);
+            }
+            wl_registry::Event::GlobalRemove { name } => {
+                if let Some(pos) = state.outputs.iter().position(|o| o.global_name == name) {
+                    let output = state.outputs.remove(pos);
+                    output.status.destroy();
+                    output.output.release();
+                    state.publish();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<WlOutput, ()> for State {
+    fn event(
+        state: &mut Self,
+        output: &WlOutput,
+        event: wl_output::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let wl_output::Event::Name { name } = event else {
+            return;
+        };
+        if let Some(output) = state.output_mut(&output.id()) {
+            output.name = Some(name);
+            state.publish();
+        }
+    }
+}
+
+impl Dispatch<ZriverOutputStatusV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        status: &ZriverOutputStatusV1,
+        event: zriver_output_status_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let Some(output) = state.outputs.iter_mut().find(|o| o.status == *status) else {
+            return;
+        };
+        match event {
+            zriver_output_status_v1::Event::FocusedTags { tags } => output.focused_tags = tags,
+            zriver_output_status_v1::Event::ViewTags { tags } => {
+                output.occupied_tags = tags
+                    .chunks_exact(4)
+                    .map(|c| u32::from_ne_bytes([c[0], c[1], c[2], c[3]]))
+                    .fold(0, |acc, tags| acc | tags);
+            }
+            zriver_output_status_v1::Event::UrgentTags { tags } => output.urgent_tags = tags,
+            _ => return,
+        }
+        state.publish();
+    }
+}
+
+impl Dispatch<ZriverSeatStatusV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        _: &ZriverSeatStatusV1,
+        event: zriver_seat_status_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let zriver_seat_status_v1::Event::FocusedOutput { output } = event {
+            state.focused_output = Some(output.id());
+            state.publish();
+        }
+    }
+}
+
+impl Dispatch<ZriverCommandCallbackV1, ()> for State {
+    fn event(
+        _: &mut Self,
+        _: &ZriverCommandCallbackV1,
+        event: zriver_command_callback_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let zriver_command_callback_v1::Event::Failure { failure_message } = event {
+            eprintln!("river command failed: {failure_message}");
+        }
+    }
+}
+
+delegate_noop!(State: ZriverStatusManagerV1);
+delegate_noop!(State: ZriverControlV1);
+delegate_noop!(State: ignore WlSeat);
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::net::UnixStream;
+
+    use super::*;
+
+    /// Queue for the objects created to get distinct outputs.
+    struct Outputs;
+
+    delegate_noop!(Outputs: ignore WlRegistry);
+    delegate_noop!(Outputs: ignore WlOutput);
+
+    fn output(wl_output: WlOutput, name: &str, focused_tags: u32, occupied_tags: u32) -> Output {
+        Output {
+            global_name: 0,
+            status: ZriverOutputStatusV1::inert(wl_output.backend().clone()),
+            output: wl_output,
+            name: Some(name.to_string()),
+            focused_tags,
+            occupied_tags,
+            urgent_tags: 0,
+        }
+    }
+
+    #[test]
+    fn publish_numbers_tags_per_output() {
+        // Nothing reads the requests, the connection only gives out ids for the outputs
+        let (socket, _compositor) = UnixStream::pair().unwrap();
+        let connection = Connection::from_socket(socket).unwrap();
+        let queue = connection.new_event_queue::<Outputs>();
+        let registry = connection.display().get_registry(&queue.handle(), ());
+        let new_output = |name| registry.bind::<WlOutput, _, _>(name, 4, &queue.handle(), ());
+
+        let (tx, rx) = watch::channel(Vec::new());
+        let state = State {
+            status_manager: ZriverStatusManagerV1::inert(connection.backend().downgrade()),
+            outputs: vec![
+                output(new_output(1), "DP-1", 0b1, 0b11),
+                output(new_output(2), "HDMI-A-1", 0b100, 0),
+            ],
+            focused_output: None,
+            tags: Default::default(),
+            focused_output_name: Default::default(),
+            tx,
+        };
+
+        state.publish();
+        let workspace_infos = rx.borrow().clone();
+        assert_eq!(workspace_infos.len(), 2 * TAG_COUNT as usize);
+        for (output, infos) in ["DP-1", "HDMI-A-1"]
+            .iter()
+            .zip(workspace_infos.chunks(TAG_COUNT as usize))
+        {
+            let idxs = infos.iter().map(|w| w.idx).collect::<Vec<_>>();
+            assert_eq!(idxs, (1..=TAG_COUNT as i32).collect::<Vec<_>>());
+            assert!(infos.iter().all(|w| w.output.as_deref() == Some(*output)));
+        }
+
+        let flags = |w: &WorkspaceInfo| (w.is_active, w.has_windows);
+        assert_eq!(flags(&workspace_infos[0]), (true, true));
+        assert_eq!(flags(&workspace_infos[1]), (false, true));
+        assert_eq!(flags(&workspace_infos[2]), (false, false));
+        assert_eq!(
+            flags(&workspace_infos[TAG_COUNT as usize + 2]),
+            (true, false)
+        );
+
+        // Ids stay unique across outputs
+        let mut ids = workspace_infos.iter().map(|w| w.id).collect::<Vec<_>>();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), workspace_infos.len());
+    }
+}
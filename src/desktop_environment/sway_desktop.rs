@@ -0,0 +1,483 @@
+//! i3 IPC backend for sway (and i3), see `man 7 sway-ipc`.
+
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use iced::futures::stream::{self, unfold};
+use iced::futures::{Stream, StreamExt};
+use serde::Deserialize;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::UnixStream;
+
+use super::{Backoff, DesktopBackend, WindowInfo, WorkspaceId, WorkspaceInfo};
+use crate::message::Message;
+
+const MAGIC: &[u8] = b"i3-ipc";
+const HEADER_LEN: usize = MAGIC.len() + 8;
+
+const RUN_COMMAND: u32 = 0;
+const GET_WORKSPACES: u32 = 1;
+const SUBSCRIBE: u32 = 2;
+const GET_TREE: u32 = 4;
+
+pub struct SwayDesktop {
+    socket_path: PathBuf,
+    /// Workspace names by id, as sway can only switch workspaces by name or number.
+    names: Arc<Mutex<HashMap<WorkspaceId, String>>>,
+}
+
+impl SwayDesktop {
+    /// Connects to the IPC socket at `$SWAYSOCK`, falling back to `$I3SOCK`.
+    pub fn connect() -> Option<Self> {
+        let socket_path = std::env::var_os("SWAYSOCK")
+            .or_else(|| std::env::var_os("I3SOCK"))
+            .map(PathBuf::from)?;
+        std::os::unix::net::UnixStream::connect(&socket_path).ok()?;
+        Some(Self::new(socket_path))
+    }
+
+    pub fn new(socket_path: PathBuf) -> Self {
+        Self {
+            socket_path,
+            names: Default::default(),
+        }
+    }
+
+    /// The name of the workspace, quoted for use in commands.
+    fn quoted_name(&self, id: WorkspaceId) -> Option<String> {
+        let name = self.names.lock().unwrap().get(&id).cloned()?;
+        let name = name.replace('\\', "\\\\").replace('"', "\\\"");
+        Some(format!("\"{name}\""))
+    }
+
+    /// Messages for the workspace and focus changes, starting with the initial state and
+    /// reconnecting with backoff whenever the IPC socket closes, e.g. on `swaymsg reload`.
+    fn events(&self) -> impl Stream<Item = Message> + Send + 'static {
+        unfold(
+            (
+                self.socket_path.clone(),
+                self.names.clone(),
+                None,
+                Backoff::default(),
+            ),
+            |(path, names, mut events, mut backoff)| async move {
+                loop {
+                    match &mut events {
+                        // Emit the current state before waiting for the first event.
+                        None => match subscribe(&path).await {
+                            Some(stream) => events = Some(stream),
+                            None => {
+                                backoff.wait().await;
+                                continue;
+                            }
+                        },
+                        Some(stream) => {
+                            if let Err(e) = read_message(stream).await {
+                                eprintln!("sway event stream disconnected: {e}");
+                                events = None;
+                                backoff.wait().await;
+                                continue;
+                            }
+                        }
+                    }
+
+                    let Some((workspace_infos, focused_window)) = read_state(&path).await else {
+                        events = None;
+                        backoff.wait().await;
+                        continue;
+                    };
+                    backoff.reset();
+                    *names.lock().unwrap() = workspace_infos
+                        .iter()
+                        .map(|(info, name)| (info.id, name.clone()))
+                        .collect();
+                    let workspace_infos =
+                        workspace_infos.into_iter().map(|(info, _)| info).collect();
+                    let messages = vec![
+                        Message::WorkspacesChanged(workspace_infos),
+                        Message::FocusedWindowChanged(focused_window),
+                    ];
+                    return Some((messages, (path, names, events, backoff)));
+                }
+            },
+        )
+        .flat_map(stream::iter)
+    }
+
+    fn run_command(&self, command: &str) -> std::io::Result<()> {
+        let mut stream = std::os::unix::net::UnixStream::connect(&self.socket_path)?;
+        stream.write_all(&encode(RUN_COMMAND, command.as_bytes()))?;
+        let mut header = [0; HEADER_LEN];
+        stream.read_exact(&mut header)?;
+        let (len, _) = decode_header(&header)?;
+        let mut reply = vec![0; len];
+        stream.read_exact(&mut reply)
+    }
+}
+
+impl DesktopBackend for SwayDesktop {
+    fn focus_workspace(&mut self, id: WorkspaceId) {
+        if let Some(name) = self.quoted_name(id) {
+            let _ = self.run_command(&format!("workspace {name}"));
+        }
+    }
+
+    fn move_window_to_workspace(&mut self, id: WorkspaceId) {
+        if let Some(name) = self.quoted_name(id) {
+            let _ = self.run_command(&format!("move container to workspace {name}"));
+        }
+    }
+
+    fn subscription(&self) -> iced::Subscription<Message> {
+        #[derive(Hash)]
+        struct SwayEvents;
+
+        iced::Subscription::run_with_id(SwayEvents, self.events())
+    }
+}
+
+fn encode(kind: u32, payload: &[u8]) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(HEADER_LEN + payload.len());
+    buf.extend_from_slice(MAGIC);
+    buf.extend_from_slice(&(payload.len() as u32).to_ne_bytes());
+    buf.extend_from_slice(&kind.to_ne_bytes());
+    buf.extend_from_slice(payload);
+    buf
+}
+
+/// Returns the payload length and message type.
+fn decode_header(header: &[u8; HEADER_LEN]) -> std::io::Result<(usize, u32)> {
+    if &header[..MAGIC.len()] != MAGIC {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "invalid i3 IPC magic",
+        ));
+    }
+    let len = u32::from_ne_bytes(header[6..10].try_into().unwrap());
+    let kind = u32::from_ne_bytes(header[10..14].try_into().unwrap());
+    Ok((len as usize, kind))
+}
+
+async fn read_message(stream: &mut UnixStream) -> std::io::Result<(u32, Vec<u8>)> {
+    let mut header = [0; HEADER_LEN];
+    stream.read_exact(&mut header).await?;
+    let (len, kind) = decode_header(&header)?;
+    let mut payload = vec![0; len];
+    stream.read_exact(&mut payload).await?;
+    Ok((kind, payload))
+}
+
+async fn request(socket_path: &Path, kind: u32, payload: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut stream = UnixStream::connect(socket_path).await?;
+    stream.write_all(&encode(kind, payload)).await?;
+    read_message(&mut stream).await.map(|(_, reply)| reply)
+}
+
+/// Opens a connection subscribed to workspace, window and output events.
+async fn subscribe(socket_path: &Path) -> Option<UnixStream> {
+    let mut stream = UnixStream::connect(socket_path).await.ok()?;
+    stream
+        .write_all(&encode(SUBSCRIBE, br#"["workspace","window","output"]"#))
+        .await
+        .ok()?;
+
+    #[derive(Deserialize)]
+    struct SubscribeReply {
+        success: bool,
+    }
+    let (_, reply) = read_message(&mut stream).await.ok()?;
+    let reply: SubscribeReply = serde_json::from_slice(&reply).ok()?;
+    reply.success.then_some(stream)
+}
+
+#[derive(Deserialize)]
+struct Workspace {
+    id: WorkspaceId,
+    name: String,
+    visible: bool,
+    urgent: bool,
+    output: String,
+}
+
+#[derive(Deserialize)]
+struct Node {
+    id: WorkspaceId,
+    #[serde(rename = "type")]
+    kind: String,
+    /// The window title, or the workspace or output name.
+    name: Option<String>,
+    #[serde(default)]
+    focused: bool,
+    #[serde(default)]
+    nodes: Vec<Node>,
+    #[serde(default)]
+    floating_nodes: Vec<Node>,
+    /// Set on Wayland windows.
+    app_id: Option<String>,
+    /// Set on X11 windows.
+    window_properties: Option<WindowProperties>,
+    /// 0 if not fullscreen, 1 if fullscreen on the workspace and 2 if fullscreen globally.
+    #[serde(default)]
+    fullscreen_mode: u8,
+}
+
+#[derive(Deserialize)]
+struct WindowProperties {
+    class: Option<String>,
+}
+
+#[derive(Default)]
+struct WorkspaceWindows {
+    tiled: usize,
+    floating: usize,
+    app_ids: Vec<String>,
+    fullscreen: bool,
+}
+
+impl Node {
+    fn is_window(&self) -> bool {
+        self.nodes.is_empty()
+            && self.floating_nodes.is_empty()
+            && matches!(self.kind.as_str(), "con" | "floating_con")
+    }
+
+    fn children(&self) -> impl Iterator<Item = &Node> {
+        self.nodes.iter().chain(&self.floating_nodes)
+    }
+
+    fn count_windows(&self) -> usize {
+        if self.is_window() {
+            1
+        } else {
+            self.children().map(Node::count_windows).sum()
+        }
+    }
+
+    fn has_fullscreen(&self) -> bool {
+        self.fullscreen_mode != 0 || self.children().any(Node::has_fullscreen)
+    }
+
+    /// The Wayland app id or X11 class of a window.
+    fn app_id(&self) -> Option<String> {
+        let class = self
+            .window_properties
+            .as_ref()
+            .and_then(|p| p.class.clone());
+        self.app_id.clone().or(class)
+    }
+
+    fn collect_app_ids(&self, app_ids: &mut Vec<String>) {
+        if self.is_window() {
+            app_ids.extend(self.app_id());
+        } else {
+            for node in self.children() {
+                node.collect_app_ids(app_ids);
+            }
+        }
+    }
+
+    /// The focused window, if focus isn't on an empty workspace.
+    fn focused_window(&self) -> Option<WindowInfo> {
+        if self.focused && self.is_window() {
+            return Some(WindowInfo {
+                app_id: self.app_id(),
+                title: self.name.clone().unwrap_or_default(),
+            });
+        }
+        self.children().find_map(Node::focused_window)
+    }
+
+    /// Collects the windows of every workspace in the tree.
+    fn workspace_windows(&self, windows: &mut HashMap<WorkspaceId, WorkspaceWindows>) {
+        if self.kind == "workspace" {
+            let mut app_ids = Vec::new();
+            self.collect_app_ids(&mut app_ids);
+            windows.insert(
+                self.id,
+                WorkspaceWindows {
+                    tiled: self.nodes.iter().map(Node::count_windows).sum(),
+                    floating: self.floating_nodes.iter().map(Node::count_windows).sum(),
+                    app_ids,
+                    fullscreen: self.children().any(Node::has_fullscreen),
+                },
+            );
+        } else {
+            for node in &self.nodes {
+                node.workspace_windows(windows);
+            }
+        }
+    }
+}
+
+/// Workspace infos along with the workspace names, and the focused window.
+async fn read_state(
+    socket_path: &Path,
+) -> Option<(Vec<(WorkspaceInfo, String)>, Option<WindowInfo>)> {
+    let workspaces = request(socket_path, GET_WORKSPACES, b"").await.ok()?;
+    let workspaces: Vec<Workspace> = serde_json::from_slice(&workspaces).ok()?;
+    let tree = request(socket_path, GET_TREE, b"").await.ok()?;
+    let tree: Node = serde_json::from_slice(&tree).ok()?;
+
+    let mut windows = HashMap::new();
+    tree.workspace_windows(&mut windows);
+
+    // Number the workspaces of each output separately, in the order sway lists them.
+    let mut next_idx = HashMap::<String, i32>::new();
+    let workspace_infos = workspaces
+        .into_iter()
+        .map(|w| {
+            let idx = next_idx.entry(w.output.clone()).or_default();
+            *idx += 1;
+            let idx = *idx;
+            let WorkspaceWindows {
+                tiled,
+                floating,
+                app_ids,
+                fullscreen,
+            } = windows.remove(&w.id).unwrap_or_default();
+            let info = WorkspaceInfo {
+                output: Some(w.output),
+                id: w.id,
+                idx,
+                name: Some(w.name.clone()),
+                app_ids,
+                is_active: w.visible,
+                is_urgent: w.urgent,
+                has_windows: tiled + floating > 0,
+                has_fullscreen: fullscreen,
+                has_tiled_windows: tiled > 0,
+                windows_unknown: false,
+                is_special: false,
+            };
+            (info, w.name)
+        })
+        .collect();
+    Some((workspace_infos, tree.focused_window()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use tokio::net::UnixListener;
+
+    use super::*;
+
+    const WORKSPACE_EVENT: u32 = 0x8000_0000;
+
+    const WORKSPACES: &str = r#"[
+        {"id":10,"name":"1","visible":true,"urgent":false,"output":"DP-1"},
+        {"id":11,"name":"2","visible":false,"urgent":true,"output":"DP-1"},
+        {"id":20,"name":"web","visible":true,"urgent":false,"output":"HDMI-A-1"}
+    ]"#;
+
+    const TREE: &str = r#"{"id":1,"type":"root","nodes":[
+        {"id":2,"type":"output","nodes":[
+            {"id":10,"type":"workspace","nodes":[
+                {"id":100,"type":"con","app_id":"foot"},
+                {"id":101,"type":"con","nodes":[
+                    {"id":102,"type":"con","name":"Mozilla Firefox","focused":true,
+                     "window_properties":{"class":"Firefox"}}
+                ]}
+            ]},
+            {"id":11,"type":"workspace","floating_nodes":[
+                {"id":110,"type":"floating_con","app_id":"mpv","fullscreen_mode":1}
+            ]}
+        ]},
+        {"id":3,"type":"output","nodes":[
+            {"id":20,"type":"workspace"}
+        ]}
+    ]}"#;
+
+    /// Serves canned replies, closing the first event subscription after one event.
+    async fn serve(listener: UnixListener) {
+        let subscriptions = Arc::new(AtomicUsize::new(0));
+        while let Ok((mut socket, _)) = listener.accept().await {
+            let subscriptions = subscriptions.clone();
+            tokio::spawn(async move {
+                let (kind, _) = read_message(&mut socket).await.ok()?;
+                let reply = match kind {
+                    GET_WORKSPACES => encode(GET_WORKSPACES, WORKSPACES.as_bytes()),
+                    GET_TREE => encode(GET_TREE, TREE.as_bytes()),
+                    SUBSCRIBE => {
+                        socket
+                            .write_all(&encode(SUBSCRIBE, br#"{"success":true}"#))
+                            .await
+                            .ok()?;
+                        if subscriptions.fetch_add(1, Ordering::SeqCst) > 0 {
+                            std::future::pending::<()>().await;
+                        }
+                        encode(WORKSPACE_EVENT, br#"{"change":"focus"}"#)
+                    }
+                    _ => return None,
+                };
+                socket.write_all(&reply).await.ok()
+            });
+        }
+    }
+
+    async fn next_message(events: &mut (impl Stream<Item = Message> + Unpin)) -> Message {
+        let message = tokio::time::timeout(Duration::from_secs(5), events.next()).await;
+        message.ok().flatten().expect("no message")
+    }
+
+    /// The workspaces, checking that the focused window follows them.
+    async fn next_workspace_infos(
+        events: &mut (impl Stream<Item = Message> + Unpin),
+    ) -> Vec<WorkspaceInfo> {
+        let Message::WorkspacesChanged(workspace_infos) = next_message(events).await else {
+            panic!("no workspaces");
+        };
+        let Message::FocusedWindowChanged(Some(window)) = next_message(events).await else {
+            panic!("no focused window");
+        };
+        assert_eq!(window.app_id.as_deref(), Some("Firefox"));
+        assert_eq!(window.title, "Mozilla Firefox");
+        workspace_infos
+    }
+
+    #[tokio::test]
+    async fn reads_workspaces_and_reconnects() {
+        let path = std::env::temp_dir().join(format!("limbo-sway-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        tokio::spawn(serve(UnixListener::bind(&path).unwrap()));
+
+        let desktop = SwayDesktop::new(path.clone());
+        let mut events = Box::pin(desktop.events());
+
+        let workspace_infos = next_workspace_infos(&mut events).await;
+        let summary = workspace_infos
+            .iter()
+            .map(|w| (w.id, w.output.as_deref().unwrap(), w.idx))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            [(10, "DP-1", 1), (11, "DP-1", 2), (20, "HDMI-A-1", 1)]
+        );
+
+        let [first, second, third] = &workspace_infos[..] else {
+            panic!("expected three workspaces");
+        };
+        assert!(first.is_active && first.has_windows && first.has_tiled_windows);
+        assert_eq!(first.app_ids, ["foot", "Firefox"]);
+        assert!(!first.has_fullscreen);
+        assert!(second.is_urgent && second.has_windows && !second.has_tiled_windows);
+        assert!(second.has_fullscreen);
+        assert!(!third.has_windows);
+        assert_eq!(
+            desktop.quoted_name(20).as_deref(),
+            Some("\"web\""),
+            "names are kept for switching workspaces"
+        );
+
+        // The workspace event, then the state after reconnecting.
+        for _ in 0..2 {
+            assert_eq!(next_workspace_infos(&mut events).await.len(), 3);
+        }
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -3,11 +3,12 @@ use std::rc::Rc;
 use iced::daemon::{Appearance, DefaultStyle};
 use iced::event::{PlatformSpecific, wayland};
 use iced::theme::Palette;
-use iced::{Color, Element, Event, Settings, Task, Theme, window};
+use iced::{Color, Element, Event, Settings, Task, Theme, mouse, window};
 
-use crate::desktop_environment::{Desktop, WorkspaceInfo};
+use crate::config::types::DesktopKind;
+use crate::desktop_environment::{Desktop, WindowInfo, WorkspaceInfo};
 use crate::message::Message;
-use crate::sections::{SysInfo, Sysmon};
+use crate::sections::{SysInfo, Sysmon, critical_commands};
 use crate::tray::{Tray, TrayItem};
 
 mod animation;
@@ -18,6 +19,7 @@ mod desktop_environment;
 mod icons;
 mod message;
 mod sections;
+mod shell;
 mod tray;
 
 use bar::Bar;
@@ -63,6 +65,10 @@ pub async fn main() -> iced::Result {
 pub struct GlobalState {
     config: Rc<Config>,
     workspace_infos: Vec<WorkspaceInfo>,
+    /// `None` until the desktop reports the focused window, which not every backend does.
+    focused_window: Option<Option<WindowInfo>>,
+    keyboard_layout: Option<String>,
+    submap: Option<String>,
     sysinfo: SysInfo,
     tray_items: Vec<TrayItem>,
 }
@@ -71,11 +77,17 @@ struct Limbo {
     global_state: GlobalState,
     bars: Vec<Bar>,
     desktop: Desktop,
+    requested_desktop: Option<DesktopKind>,
+    /// A detection attempt is running, so don't start another one.
+    detecting_desktop: bool,
+    /// Pixels scrolled over workspaces that didn't switch workspaces yet.
+    workspace_scroll: f32,
     tray: Tray,
 }
 
 impl Limbo {
     fn new(config: Config) -> (Self, Task<Message>) {
+        let requested_desktop = Desktop::requested(config.general.desktop);
         (
             Self {
                 global_state: GlobalState {
@@ -83,10 +95,14 @@ impl Limbo {
                     ..Default::default()
                 },
                 bars: Vec::new(),
-                desktop: Desktop::new(),
+                // Connected by the first detection, which like the retries runs in the background
+                desktop: Desktop::default(),
+                requested_desktop,
+                detecting_desktop: false,
+                workspace_scroll: 0.,
                 tray: Tray::new(),
             },
-            Task::none(),
+            Task::done(Message::DetectDesktop),
         )
     }
 
@@ -97,7 +113,8 @@ impl Limbo {
                     wayland::Event::Output(_, _)
                     | wayland::Event::Layer(wayland::LayerEvent::Done, _, _),
                 ))
-                | Event::Window(window::Event::Opened { .. }) => {
+                | Event::Window(window::Event::Opened { .. })
+                | Event::Mouse(mouse::Event::CursorEntered | mouse::Event::CursorLeft) => {
                     Some(Message::Iced(window_id, evt))
                 }
                 _ => None,
@@ -111,17 +128,27 @@ impl Limbo {
             subscriptions.push(animation::subscription());
         }
 
+        // Keep looking for a compositor, e.g. when started before its socket exists
+        if self.desktop.should_retry(self.requested_desktop) {
+            subscriptions.push(
+                iced::time::every(std::time::Duration::from_secs(2))
+                    .map(|_| Message::DetectDesktop),
+            );
+        }
+
         subscriptions.extend(self.bars.iter().map(|bar| bar.subscription()));
 
         iced::Subscription::batch(subscriptions)
     }
 
     fn update(&mut self, message: Message) -> Task<Message> {
-        for bar in self.bars.iter_mut() {
-            bar.update(&message);
-        }
+        let bar_tasks = self
+            .bars
+            .iter_mut()
+            .map(|bar| bar.update(&message))
+            .collect::<Vec<_>>();
 
-        match message {
+        let task = match message {
             Message::Iced(_, Event::PlatformSpecific(PlatformSpecific::Wayland(evt))) => {
                 match evt {
                     wayland::Event::Output(
@@ -153,15 +180,83 @@ impl Limbo {
                 self.global_state.workspace_infos = workspace_infos;
                 Task::none()
             }
+            Message::FocusedWindowChanged(focused_window) => {
+                self.global_state.focused_window = Some(focused_window);
+                Task::none()
+            }
+            Message::KeyboardLayoutChanged(keyboard_layout) => {
+                self.global_state.keyboard_layout = keyboard_layout;
+                Task::none()
+            }
+            Message::SubmapChanged(submap) => {
+                self.global_state.submap = submap;
+                Task::none()
+            }
+            Message::CycleKeyboardLayout => {
+                self.desktop.cycle_keyboard_layout();
+                Task::none()
+            }
+            Message::DetectDesktop => {
+                if self.desktop.should_retry(self.requested_desktop) && !self.detecting_desktop {
+                    self.detecting_desktop = true;
+                    Task::perform(
+                        Desktop::detect(self.requested_desktop),
+                        Message::DesktopDetected,
+                    )
+                } else {
+                    Task::none()
+                }
+            }
+            Message::DesktopDetected(detected) => {
+                self.detecting_desktop = false;
+                if let Some(desktop) = detected.take()
+                    && desktop.is_connected()
+                {
+                    self.desktop = desktop;
+                }
+                Task::none()
+            }
             Message::FocusWorkspace(id) => {
                 self.desktop.focus_workspace(id);
                 Task::none()
             }
-            Message::CycleWorkspace { forward } => {
-                self.desktop.cycle_workspace(forward);
+            Message::ScrollWorkspaces { output, delta } => {
+                let scroll = &self.global_state.config.bar.workspaces.scroll;
+                // Start over when changing direction
+                if self.workspace_scroll.signum() != delta.signum() {
+                    self.workspace_scroll = 0.;
+                }
+                self.workspace_scroll += delta;
+                if self.workspace_scroll.abs() >= scroll.threshold {
+                    self.workspace_scroll = 0.;
+                    self.desktop.cycle_workspace(
+                        &self.global_state.workspace_infos,
+                        &output,
+                        delta < 0.,
+                        scroll.wrap,
+                    );
+                }
+                Task::none()
+            }
+            Message::MoveWindowToWorkspace(id) => {
+                self.desktop.move_window_to_workspace(id);
+                Task::none()
+            }
+            Message::ToggleOverview => {
+                self.desktop.toggle_overview();
                 Task::none()
             }
             Message::SysinfoUpdate(sysinfo) => {
+                let commands = critical_commands(
+                    &self.global_state.config,
+                    &self.global_state.sysinfo,
+                    &sysinfo,
+                );
+                for command in commands {
+                    if let Err(e) = shell::command(command).spawn() {
+                        eprintln!("Failed to run `{command}`: {e}");
+                    }
+                }
                 self.global_state.sysinfo = sysinfo;
                 Task::none()
             }
@@ -170,7 +265,8 @@ impl Limbo {
                 Task::none()
             }
             _ => Task::none(),
-        }
+        };
+        Task::batch(bar_tasks.into_iter().chain([task]))
     }
 
     fn view(&self, window_id: window::Id) -> Element<'_, Message> {
@@ -1,7 +1,7 @@
 use iced::id::Id;
 use iced::{Event, window};
 
-use crate::desktop_environment::{WorkspaceId, WorkspaceInfo};
+use crate::desktop_environment::{Detected, WindowInfo, WorkspaceId, WorkspaceInfo};
 use crate::sections::SysInfo;
 
 #[derive(Debug, Clone)]
@@ -10,7 +10,21 @@ pub enum Message {
 
     WorkspacesChanged(Vec<WorkspaceInfo>),
     FocusWorkspace(WorkspaceId),
-    CycleWorkspace { forward: bool },
+    /// Scrolled over the workspaces of `output`, in pixels.
+    ScrollWorkspaces {
+        output: String,
+        delta: f32,
+    },
+    MoveWindowToWorkspace(WorkspaceId),
+    ToggleOverview,
+    DetectDesktop,
+    DesktopDetected(Detected),
+    FocusedWindowChanged(Option<WindowInfo>),
+    /// The compositor's name for the active keyboard layout.
+    KeyboardLayoutChanged(Option<String>),
+    CycleKeyboardLayout,
+    /// The active Hyprland submap, or `None` for the default one.
+    SubmapChanged(Option<String>),
 
     ClockToggleExpanded(Id),
     ClockTick(jiff::Zoned),
@@ -1,8 +1,7 @@
 use std::rc::Rc;
 
-use iced::Alignment;
 use iced::id::Id;
-use iced::widget::{mouse_area, row, text};
+use iced::widget::{mouse_area, text};
 
 use crate::GlobalState;
 use crate::config::Config;
@@ -40,7 +39,12 @@ impl Clock {
     }
 
     pub fn view(&self) -> iced::Element<'_, Message> {
+        let vertical = self.config.bar.position.is_vertical();
         let format = match (self.config.general.time_format, self.expanded) {
+            // 5\n14\nPM
+            (TimeFormat::_12h, false) if vertical => "%-I\n%M\n%p",
+            // 22\n14
+            (TimeFormat::_24h, false) if vertical => "%k\n%M",
             // Sun 5:14 PM
             (TimeFormat::_12h, false) => "%a %-I:%M %p",
             // Sunday, Jun 22 5:14:34 PM
@@ -52,16 +56,13 @@ impl Clock {
         };
         let formatted_date = self.now.strftime(format).to_string();
 
-        mouse_area(
-            self.config.section(
-                row![
-                    self.config.icon(&self.config.bar.clock.icon),
-                    text(formatted_date)
-                ]
-                .align_y(Alignment::Center)
-                .spacing(8),
-            ),
-        )
+        mouse_area(self.config.section(self.config.flow(
+            8,
+            [
+                self.config.icon(&self.config.bar.clock.icon).into(),
+                text(formatted_date).center().into(),
+            ],
+        )))
         .on_press(Message::ClockToggleExpanded(self.id.clone()))
         .into()
     }
@@ -0,0 +1,51 @@
+use std::rc::Rc;
+
+use iced::widget::{mouse_area, text};
+
+use crate::GlobalState;
+use crate::config::Config;
+use crate::message::Message;
+
+pub struct KeyboardLayout {
+    config: Rc<Config>,
+    layout: Option<String>,
+}
+
+impl KeyboardLayout {
+    pub fn new(global_state: &GlobalState) -> Self {
+        Self {
+            config: global_state.config.clone(),
+            layout: global_state.keyboard_layout.clone(),
+        }
+    }
+
+    pub fn update(&mut self, message: &Message) {
+        if let Message::KeyboardLayoutChanged(layout) = message {
+            self.layout = layout.clone();
+        }
+    }
+
+    pub fn view(&self) -> iced::Element<'_, Message> {
+        let cfg = &self.config.bar.keyboard_layout;
+        let short_name = self
+            .layout
+            .as_deref()
+            .map(|layout| cfg.short_name(layout))
+            .unwrap_or_default();
+
+        mouse_area(self.config.section(self.config.flow(
+            8,
+            [
+                self.config.icon(&cfg.icon).into(),
+                text(short_name).center().into(),
+            ],
+        )))
+        .on_press(Message::CycleKeyboardLayout)
+        .into()
+    }
+
+    /// Whether the layout is unknown, e.g. because the desktop backend doesn't report it.
+    pub fn is_empty(&self) -> bool {
+        self.layout.is_none()
+    }
+}
@@ -1,9 +1,15 @@
 mod clock;
+mod keyboard_layout;
 mod quick_settings;
+mod submap;
 mod sysmon;
+mod window_title;
 mod workspaces;
 
 pub use clock::Clock;
+pub use keyboard_layout::KeyboardLayout;
 pub use quick_settings::TrayView;
-pub use sysmon::{SysInfo, Sysmon};
+pub use submap::Submap;
+pub use sysmon::{SysInfo, Sysmon, critical_commands};
+pub use window_title::WindowTitle;
 pub use workspaces::Workspaces;
@@ -2,8 +2,6 @@
 
 use std::rc::Rc;
 
-use iced::widget::Row;
-
 use crate::GlobalState;
 use crate::components::system_icon;
 use crate::config::Config;
@@ -37,6 +35,6 @@ impl TrayView {
             .filter_map(|item| system_icon(item.item.icon_name.as_ref()?))
             .collect::<Vec<_>>();
 
-        self.config.section(Row::from_vec(icons).spacing(12)).into()
+        self.config.section(self.config.flow(12, icons)).into()
     }
 }
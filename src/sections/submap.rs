@@ -0,0 +1,40 @@
+use std::rc::Rc;
+
+use crate::GlobalState;
+use crate::config::Config;
+use crate::message::Message;
+
+pub struct Submap {
+    config: Rc<Config>,
+    submap: Option<String>,
+}
+
+impl Submap {
+    pub fn new(global_state: &GlobalState) -> Self {
+        Self {
+            config: global_state.config.clone(),
+            submap: global_state.submap.clone(),
+        }
+    }
+
+    pub fn update(&mut self, message: &Message) {
+        if let Message::SubmapChanged(submap) = message {
+            self.submap = submap.clone();
+        }
+    }
+
+    pub fn view(&self) -> iced::Element<'_, Message> {
+        let submap = self.submap.as_deref().unwrap_or_default();
+        self.config
+            .section(
+                self.config
+                    .text_with_icon(&self.config.bar.submap.icon, submap),
+            )
+            .into()
+    }
+
+    /// Whether the default submap is active.
+    pub fn is_empty(&self) -> bool {
+        self.submap.is_none()
+    }
+}
@@ -0,0 +1,196 @@
+//! Disk space from `sysinfo::Disks` and throughput from `/proc/diskstats`.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use sysinfo::Disks;
+
+/// `/proc/diskstats` counts sectors of 512 bytes, whatever the device's sector size.
+const SECTOR_SIZE: f64 = 512.;
+
+#[derive(Debug, Clone)]
+pub struct DiskInfo {
+    pub mount_point: PathBuf,
+    /// Used and total space in GB.
+    pub used: f32,
+    pub total: f32,
+    /// Read and write throughput in bytes per second, known from the second probe on.
+    pub io: Option<(f32, f32)>,
+}
+
+impl DiskInfo {
+    pub fn used_percent(&self) -> f32 {
+        if self.total > 0. {
+            self.used / self.total * 100.
+        } else {
+            0.
+        }
+    }
+}
+
+pub struct DiskProbe {
+    mount_points: Vec<PathBuf>,
+    disks: Disks,
+    /// Path of `diskstats`, normally `/proc/diskstats`.
+    diskstats: PathBuf,
+    /// Sectors read and written by device at the previous probe.
+    previous: HashMap<String, (u64, u64)>,
+    previous_time: Option<Instant>,
+}
+
+impl DiskProbe {
+    pub fn new(mount_points: Vec<PathBuf>, diskstats: impl Into<PathBuf>) -> Self {
+        Self {
+            mount_points,
+            disks: Disks::new_with_refreshed_list(),
+            diskstats: diskstats.into(),
+            previous: HashMap::new(),
+            previous_time: None,
+        }
+    }
+
+    /// Reads the configured mount points that are mounted, in order.
+    pub fn read(&mut self) -> Vec<DiskInfo> {
+        self.disks.refresh(true);
+
+        let now = Instant::now();
+        let stats = read_diskstats(&self.diskstats);
+        let elapsed = self
+            .previous_time
+            .map(|previous| now.duration_since(previous).as_secs_f64())
+            .filter(|elapsed| *elapsed > 0.);
+
+        let infos = self
+            .mount_points
+            .iter()
+            .filter_map(|mount_point| {
+                let disk = self
+                    .disks
+                    .list()
+                    .iter()
+                    .find(|disk| disk.mount_point() == mount_point)?;
+                let total = disk.total_space();
+                let used = total.saturating_sub(disk.available_space());
+                let io = device_name(Path::new(disk.name()))
+                    .and_then(|device| io_rates(&self.previous, &stats, &device, elapsed?));
+                Some(DiskInfo {
+                    mount_point: mount_point.clone(),
+                    used: (used as f64 / 1e9) as f32,
+                    total: (total as f64 / 1e9) as f32,
+                    io,
+                })
+            })
+            .collect();
+
+        self.previous = stats;
+        self.previous_time = Some(now);
+        infos
+    }
+}
+
+/// Name of a device in `diskstats`, resolving symlinks such as `/dev/mapper/root` to `dm-0`.
+fn device_name(device: &Path) -> Option<String> {
+    let device = device
+        .canonicalize()
+        .unwrap_or_else(|_| device.to_path_buf());
+    Some(device.file_name()?.to_string_lossy().into_owned())
+}
+
+/// Read and write throughput of `device` in bytes per second, between two samples of
+/// `diskstats` taken `elapsed` seconds apart.
+fn io_rates(
+    previous: &HashMap<String, (u64, u64)>,
+    current: &HashMap<String, (u64, u64)>,
+    device: &str,
+    elapsed: f64,
+) -> Option<(f32, f32)> {
+    let (read, written) = current.get(device)?;
+    let (previous_read, previous_written) = previous.get(device)?;
+    // Counters start over when a device is removed and added again
+    let rate = |sectors: u64, previous: u64| {
+        (sectors.saturating_sub(previous) as f64 * SECTOR_SIZE / elapsed) as f32
+    };
+    Some((
+        rate(*read, *previous_read),
+        rate(*written, *previous_written),
+    ))
+}
+
+/// Sectors read and written by device.
+fn read_diskstats(path: &Path) -> HashMap<String, (u64, u64)> {
+    let Ok(diskstats) = std::fs::read_to_string(path) else {
+        return HashMap::new();
+    };
+    diskstats
+        .lines()
+        .filter_map(|line| {
+            // major minor name reads merged sectors-read ms writes merged sectors-written ...
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            let name = fields.get(2)?;
+            let read = fields.get(5)?.parse().ok()?;
+            let written = fields.get(9)?.parse().ok()?;
+            Some((name.to_string(), (read, written)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DISKSTATS: &str = "\
+ 259       0 nvme0n1 1000 10 2000 300 4000 20 8000 500 0 600 800 0 0 0 0 0 0
+ 259       1 nvme0n1p1 100 0 200 30 400 0 800 50 0 60 80 0 0 0 0 0 0
+ 253       0 dm-0 10 0 20 3 40 0 80 5 0 6 8 0 0 0 0 0 0
+   8       0 sda short
+";
+
+    fn diskstats(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("limbo-{name}-{}", std::process::id()));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn reads_sectors_by_device() {
+        let path = diskstats("diskstats", DISKSTATS);
+        let stats = read_diskstats(&path);
+        assert_eq!(stats.len(), 3, "lines with missing fields are skipped");
+        assert_eq!(stats["nvme0n1"], (2000, 8000));
+        assert_eq!(stats["dm-0"], (20, 80));
+        let _ = std::fs::remove_file(&path);
+
+        assert!(read_diskstats(Path::new("/nonexistent/diskstats")).is_empty());
+    }
+
+    #[test]
+    fn rates_are_deltas_over_the_elapsed_time() {
+        let previous = HashMap::from([("sda".to_string(), (1000, 5000))]);
+        let current = HashMap::from([
+            ("sda".to_string(), (3000, 5000)),
+            ("sdb".to_string(), (10, 10)),
+        ]);
+        // 2000 sectors of 512 bytes in 2 seconds
+        assert_eq!(
+            io_rates(&previous, &current, "sda", 2.),
+            Some((512_000., 0.))
+        );
+        // Not in the previous sample, e.g. just plugged in
+        assert_eq!(io_rates(&previous, &current, "sdb", 2.), None);
+        // Counters that went back, e.g. after the device was added again
+        assert_eq!(io_rates(&current, &previous, "sda", 2.), Some((0., 0.)));
+    }
+
+    #[test]
+    fn the_first_probe_has_no_rates() {
+        // No mount point is configured, so only the diskstats samples are taken
+        let path = diskstats("diskstats-probe", DISKSTATS);
+        let mut probe = DiskProbe::new(Vec::new(), &path);
+        assert!(probe.previous_time.is_none());
+        assert!(probe.read().is_empty());
+        assert!(probe.previous_time.is_some());
+        assert_eq!(probe.previous["nvme0n1"], (2000, 8000));
+        let _ = std::fs::remove_file(&path);
+    }
+}
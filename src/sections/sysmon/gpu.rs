@@ -0,0 +1,227 @@
+//! GPU stats read from the DRM and hwmon sysfs interfaces, or from `nvidia-smi` for the
+//! proprietary NVIDIA driver, which doesn't expose them in sysfs.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::types::GpuSource;
+
+/// hwmon drivers of GPUs, whose `temp1_input` is the GPU temperature.
+const HWMON_DRIVERS: &[&str] = &["amdgpu", "i915", "xe", "nouveau"];
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct GpuInfo {
+    /// Utilisation in percent.
+    pub usage: Option<f32>,
+    /// Used and total VRAM in GB.
+    pub vram: Option<(f32, f32)>,
+    pub temp: Option<f32>,
+}
+
+pub struct GpuProbe {
+    /// Mount point of sysfs, normally `/sys`.
+    root: PathBuf,
+    /// DRM card to read, or the first one with stats.
+    card: Option<String>,
+    source: GpuSource,
+    /// Device directory of the card, found on the first read.
+    device: Option<PathBuf>,
+    /// `nvidia-smi` couldn't be run or failed, e.g. without NVIDIA hardware, so don't try again.
+    nvidia_smi_failed: bool,
+}
+
+impl GpuProbe {
+    pub fn new(root: impl Into<PathBuf>, card: Option<String>, source: GpuSource) -> Self {
+        Self {
+            root: root.into(),
+            card,
+            source,
+            device: None,
+            nvidia_smi_failed: false,
+        }
+    }
+
+    pub async fn read(&mut self) -> Option<GpuInfo> {
+        if self.source != GpuSource::NvidiaSmi
+            && let Some(info) = self.read_sysfs()
+        {
+            return Some(info);
+        }
+        if self.source != GpuSource::Sysfs && !self.nvidia_smi_failed {
+            match read_nvidia_smi().await {
+                Ok(info) => return info,
+                Err(e) => {
+                    if self.source == GpuSource::NvidiaSmi {
+                        eprintln!("Failed to run nvidia-smi: {e}");
+                    }
+                    self.nvidia_smi_failed = true;
+                }
+            }
+        }
+        None
+    }
+
+    fn read_sysfs(&mut self) -> Option<GpuInfo> {
+        if self.device.is_none() {
+            self.device = self.find_device();
+        }
+        let device = self.device.as_ref()?;
+
+        let usage = read_number(&device.join("gpu_busy_percent")).map(|usage| usage as f32);
+        let vram = read_number(&device.join("mem_info_vram_used"))
+            .zip(read_number(&device.join("mem_info_vram_total")))
+            .map(|(used, total)| ((used / 1e9) as f32, (total / 1e9) as f32));
+        let temp = hwmon(device)
+            .and_then(|hwmon| read_number(&hwmon.join("temp1_input")))
+            .map(|temp| (temp / 1000.) as f32);
+
+        (usage.is_some() || vram.is_some() || temp.is_some()).then_some(GpuInfo {
+            usage,
+            vram,
+            temp,
+        })
+    }
+
+    /// The device directory of the configured card, or of the first card with stats.
+    fn find_device(&self) -> Option<PathBuf> {
+        let drm = self.root.join("class/drm");
+        let mut cards = fs::read_dir(&drm)
+            .ok()?
+            .filter_map(Result::ok)
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .filter(|name| match &self.card {
+                Some(card) => name == card,
+                None => is_card(name),
+            })
+            .collect::<Vec<_>>();
+        cards.sort();
+        cards
+            .into_iter()
+            .map(|card| drm.join(card).join("device"))
+            .find(|device| device.join("gpu_busy_percent").exists() || hwmon(device).is_some())
+    }
+}
+
+/// Whether `name` is a card rather than one of its connectors, e.g. `card1` but not `card1-DP-1`.
+fn is_card(name: &str) -> bool {
+    name.strip_prefix("card")
+        .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// The hwmon directory of a GPU driver under `device`.
+fn hwmon(device: &Path) -> Option<PathBuf> {
+    fs::read_dir(device.join("hwmon"))
+        .ok()?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .find(|path| {
+            fs::read_to_string(path.join("name"))
+                .is_ok_and(|name| HWMON_DRIVERS.contains(&name.trim()))
+        })
+}
+
+fn read_number(path: &Path) -> Option<f64> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// Reads the first GPU's stats, returning an error if `nvidia-smi` can't be run or fails.
+async fn read_nvidia_smi() -> std::io::Result<Option<GpuInfo>> {
+    let output = tokio::process::Command::new("nvidia-smi")
+        .args([
+            "--query-gpu=utilization.gpu,memory.used,memory.total,temperature.gpu",
+            "--format=csv,noheader,nounits",
+        ])
+        .output()
+        .await?;
+    if !output.status.success() {
+        return Err(std::io::Error::other(format!(
+            "exited with {}",
+            output.status
+        )));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let Some(line) = stdout.lines().next() else {
+        return Ok(None);
+    };
+    let fields = line
+        .split(',')
+        .map(|field| field.trim().parse::<f32>().ok())
+        .collect::<Vec<_>>();
+    let field = |i: usize| fields.get(i).copied().flatten();
+    // Memory is reported in MiB
+    let mib_to_gb = |mib: f32| mib * 1_048_576. / 1e9;
+    Ok(Some(GpuInfo {
+        usage: field(0),
+        vram: field(1)
+            .zip(field(2))
+            .map(|(used, total)| (mib_to_gb(used), mib_to_gb(total))),
+        temp: field(3),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A sysfs tree under the temporary directory with an amdgpu `card0` and, if `card1` is
+    /// set, a second card with only a busy percentage.
+    fn sysfs(name: &str, card1: bool) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("limbo-sysfs-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let device = root.join("class/drm/card0/device");
+        let hwmon = device.join("hwmon/hwmon3");
+        fs::create_dir_all(&hwmon).unwrap();
+        fs::write(device.join("gpu_busy_percent"), "42\n").unwrap();
+        fs::write(device.join("mem_info_vram_used"), "2000000000\n").unwrap();
+        fs::write(device.join("mem_info_vram_total"), "8000000000\n").unwrap();
+        fs::write(hwmon.join("name"), "amdgpu\n").unwrap();
+        fs::write(hwmon.join("temp1_input"), "55000\n").unwrap();
+        // A connector, which isn't a card
+        fs::create_dir_all(root.join("class/drm/card0-DP-1")).unwrap();
+        if card1 {
+            let device = root.join("class/drm/card1/device");
+            fs::create_dir_all(&device).unwrap();
+            fs::write(device.join("gpu_busy_percent"), "7\n").unwrap();
+        }
+        root
+    }
+
+    #[tokio::test]
+    async fn reads_the_first_card() {
+        let root = sysfs("first", true);
+        let info = GpuProbe::new(&root, None, GpuSource::Sysfs)
+            .read()
+            .await
+            .unwrap();
+        assert_eq!(info.usage, Some(42.));
+        assert_eq!(info.vram, Some((2., 8.)));
+        assert_eq!(info.temp, Some(55.));
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn reads_the_configured_card() {
+        let root = sysfs("override", true);
+        let info = GpuProbe::new(&root, Some("card1".to_string()), GpuSource::Sysfs)
+            .read()
+            .await
+            .unwrap();
+        assert_eq!(info.usage, Some(7.));
+        assert_eq!(info.vram, None);
+        assert_eq!(info.temp, None);
+
+        let missing = GpuProbe::new(&root, Some("card2".to_string()), GpuSource::Sysfs);
+        assert!(missing.find_device().is_none());
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn only_cards_are_cards() {
+        assert!(is_card("card0"));
+        assert!(is_card("card12"));
+        assert!(!is_card("card1-DP-1"));
+        assert!(!is_card("card"));
+        assert!(!is_card("renderD128"));
+    }
+}
@@ -0,0 +1,784 @@
+use std::collections::VecDeque;
+use std::hash::Hash;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::Duration;
+
+use iced::Color;
+use iced::futures::StreamExt;
+use iced::widget::{Text, text};
+use sysinfo::{CpuRefreshKind, MemoryRefreshKind, RefreshKind, System};
+
+use crate::GlobalState;
+use crate::animation::{Eased, Easing};
+use crate::components::{Sparkline, icon};
+use crate::config::Config;
+use crate::config::types::{GpuSource, Icon, SysmonSegment, TempAggregation, Thresholds, Unit};
+use crate::message::Message;
+
+mod disk;
+mod gpu;
+mod net;
+mod temp;
+use disk::{DiskInfo, DiskProbe};
+use gpu::{GpuInfo, GpuProbe};
+use net::{NetInfo, NetProbe};
+use temp::TempProbe;
+
+/// Size of history graphs along a horizontal bar.
+const GRAPH_WIDTH: f32 = 32.;
+/// Size of graphs along a vertical bar, which is only as wide as a section.
+const VERTICAL_GRAPH_WIDTH: f32 = 24.;
+const GRAPH_HEIGHT: f32 = 14.;
+/// Width of each core's bar in per-core graphs.
+const CORE_BAR_WIDTH: f32 = 3.;
+/// How much blinking segments fade out, from 0 to 1.
+const BLINK_FADE: f32 = 0.7;
+
+#[derive(Debug, Default, Clone)]
+pub struct SysInfo {
+    cpu_usage: f32,
+    /// Usage of every core, in percent.
+    cpu_cores: Vec<f32>,
+    /// In °C, `None` if the temp segment is disabled or no sensor matched.
+    cpu_temp: Option<f32>,
+    ram: f32,
+    ram_total: f32,
+    /// `None` if the GPU segment is disabled or no GPU was found.
+    gpu: Option<GpuInfo>,
+    /// Configured mount points that are mounted.
+    disks: Vec<DiskInfo>,
+    /// `None` if the net segment is disabled or the interface wasn't found.
+    net: Option<NetInfo>,
+    history: History,
+}
+
+impl SysInfo {
+    /// Level of a segment, or of its fullest mount point for disks.
+    fn level(&self, config: &Config, segment: &SysmonSegment) -> Level {
+        let value = match segment {
+            SysmonSegment::Cpu => Some(self.cpu_usage),
+            // Thresholds are in the displayed unit
+            SysmonSegment::Temp => self.cpu_temp.map(|temp| temperature(config, temp)),
+            SysmonSegment::Ram => (self.ram_total > 0.).then(|| self.ram / self.ram_total * 100.),
+            SysmonSegment::Gpu => self.gpu.and_then(|gpu| gpu.usage),
+            SysmonSegment::Disk => {
+                return self
+                    .disks
+                    .iter()
+                    .map(|disk| disk_level(config, disk))
+                    .max()
+                    .unwrap_or(Level::Normal);
+            }
+            SysmonSegment::Net => self.net.as_ref().map(|net| net.down.max(net.up)),
+        };
+        match thresholds(config, segment) {
+            Some(thresholds) => Level::new(value, thresholds.warning, thresholds.critical),
+            None => Level::Normal,
+        }
+    }
+}
+
+/// How a value compares to its segment's thresholds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Level {
+    Normal,
+    Warning,
+    Critical,
+}
+
+impl Level {
+    fn new(value: Option<f32>, warning: Option<f32>, critical: Option<f32>) -> Self {
+        let Some(value) = value else {
+            return Self::Normal;
+        };
+        if critical.is_some_and(|critical| value >= critical) {
+            Self::Critical
+        } else if warning.is_some_and(|warning| value >= warning) {
+            Self::Warning
+        } else {
+            Self::Normal
+        }
+    }
+}
+
+fn thresholds<'a>(config: &'a Config, segment: &SysmonSegment) -> Option<&'a Thresholds> {
+    let cfg = &config.bar.sysmon;
+    match segment {
+        SysmonSegment::Cpu => Some(&cfg.cpu.thresholds),
+        SysmonSegment::Temp => Some(&cfg.temp.thresholds),
+        SysmonSegment::Ram => Some(&cfg.ram.thresholds),
+        SysmonSegment::Gpu => Some(&cfg.gpu.thresholds),
+        SysmonSegment::Disk => Some(&cfg.disk.thresholds),
+        SysmonSegment::Net => Some(&cfg.net.thresholds),
+    }
+}
+
+/// Converts a temperature to the configured unit.
+fn temperature(config: &Config, celsius: f32) -> f32 {
+    match config.general.unit {
+        Unit::Metric => celsius,
+        Unit::Imperial => celsius * 9. / 5. + 32.,
+    }
+}
+
+/// Converts a temperature in the configured unit back to °C.
+fn celsius(config: &Config, temperature: f32) -> f32 {
+    match config.general.unit {
+        Unit::Metric => temperature,
+        Unit::Imperial => (temperature - 32.) * 5. / 9.,
+    }
+}
+
+/// Level of a mount point, whose own thresholds override the disk segment's.
+fn disk_level(config: &Config, disk: &DiskInfo) -> Level {
+    let cfg = &config.bar.sysmon.disk;
+    let mount = cfg.mounts.iter().find(|m| m.path == disk.mount_point);
+    Level::new(
+        Some(disk.used_percent()),
+        mount.and_then(|m| m.warning).or(cfg.thresholds.warning),
+        mount.and_then(|m| m.critical).or(cfg.thresholds.critical),
+    )
+}
+
+/// Commands of the segments that crossed into critical between two probes.
+pub fn critical_commands<'a>(
+    config: &'a Config,
+    previous: &SysInfo,
+    current: &SysInfo,
+) -> Vec<&'a str> {
+    let crossed =
+        |previous: Level, current: Level| previous != Level::Critical && current == Level::Critical;
+    config
+        .bar
+        .sysmon
+        .segments
+        .iter()
+        .filter(|segment| match segment {
+            // Mount points cross on their own
+            SysmonSegment::Disk => current.disks.iter().any(|disk| {
+                let previous_level = previous
+                    .disks
+                    .iter()
+                    .find(|d| d.mount_point == disk.mount_point)
+                    .map_or(Level::Normal, |d| disk_level(config, d));
+                crossed(previous_level, disk_level(config, disk))
+            }),
+            _ => crossed(
+                previous.level(config, segment),
+                current.level(config, segment),
+            ),
+        })
+        .filter_map(|segment| thresholds(config, segment)?.critical_command.as_deref())
+        .collect()
+}
+
+/// Recent samples of each metric.
+#[derive(Debug, Default, Clone)]
+struct History {
+    cpu_usage: RingBuffer,
+    cpu_temp: RingBuffer,
+    ram: RingBuffer,
+}
+
+impl History {
+    fn new(capacity: usize) -> Self {
+        Self {
+            cpu_usage: RingBuffer::new(capacity),
+            cpu_temp: RingBuffer::new(capacity),
+            ram: RingBuffer::new(capacity),
+        }
+    }
+}
+
+/// The last `capacity` samples pushed, oldest first.
+#[derive(Debug, Default, Clone)]
+pub struct RingBuffer {
+    samples: VecDeque<f32>,
+    capacity: usize,
+}
+
+impl RingBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, sample: f32) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = f32> + '_ {
+        self.samples.iter().copied()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+}
+
+#[derive(Debug)]
+pub struct Sysmon {
+    config: Rc<Config>,
+    info: SysInfo,
+    /// Critical segments with `blink` fade in and out.
+    blink: Eased<f32>,
+}
+
+impl Sysmon {
+    pub fn new(global_state: &GlobalState) -> Self {
+        Self {
+            config: global_state.config.clone(),
+            info: global_state.sysinfo.clone(),
+            blink: Eased::new(0, Easing::Smoothstep, 500., &[0., 1.]),
+        }
+    }
+
+    pub fn update(&mut self, message: &Message) {
+        match message {
+            Message::SysinfoUpdate(info) => self.info = info.clone(),
+            Message::AnimationTick => {
+                self.blink.update();
+                // Keep blinking while critical, and fade back in once it isn't
+                if !self.blink.is_running() {
+                    if self.is_blinking() {
+                        let target_idx = if self.blink.get() > 0.5 { 0 } else { 1 };
+                        self.blink.set_target_idx(target_idx);
+                    } else {
+                        self.blink.set_target_idx(0);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn animation_running(&self) -> bool {
+        self.is_blinking() || self.blink.is_running()
+    }
+
+    fn is_blinking(&self) -> bool {
+        self.config.bar.sysmon.segments.iter().any(|segment| {
+            thresholds(&self.config, segment).is_some_and(|thresholds| thresholds.blink)
+                && self.info.level(&self.config, segment) == Level::Critical
+        })
+    }
+
+    pub fn view(&self) -> iced::Element<'_, Message> {
+        let cfg = &self.config.bar.sysmon;
+
+        let segments = cfg.segments.iter().map(|segment| match segment {
+            SysmonSegment::Cpu => {
+                let mut content = vec![self.icon_text(
+                    &cfg.cpu.icon,
+                    self.segment_color(segment),
+                    text(format!("{:.*}%", cfg.cpu.precision, self.info.cpu_usage)),
+                )];
+                if cfg.cpu.graph {
+                    content.push(self.history_graph(
+                        &self.info.history.cpu_usage,
+                        100.,
+                        &cfg.cpu.icon,
+                    ));
+                }
+                if cfg.cpu.per_core {
+                    content.push(self.per_core_graph(&cfg.cpu.icon));
+                }
+                self.config.flow(6, content)
+            }
+            SysmonSegment::Temp => {
+                let Some(cpu_temp) = self.info.cpu_temp else {
+                    return self.config.text_with_icon(&cfg.temp.icon, "N/A");
+                };
+                let mut content = vec![self.icon_text(
+                    &cfg.temp.icon,
+                    self.segment_color(segment),
+                    text(format!(
+                        "{:.*}°",
+                        cfg.temp.precision,
+                        temperature(&self.config, cpu_temp)
+                    )),
+                )];
+                if cfg.temp.graph {
+                    content.push(self.history_graph(
+                        &self.info.history.cpu_temp,
+                        self.temp_graph_max(),
+                        &cfg.temp.icon,
+                    ));
+                }
+                self.config.flow(6, content)
+            }
+            SysmonSegment::Ram => {
+                let mut content = vec![self.icon_text(
+                    &cfg.ram.icon,
+                    self.segment_color(segment),
+                    text(format!("{:.*} GB", cfg.ram.precision, self.info.ram)),
+                )];
+                if cfg.ram.graph {
+                    content.push(self.history_graph(
+                        &self.info.history.ram,
+                        self.info.ram_total,
+                        &cfg.ram.icon,
+                    ));
+                }
+                self.config.flow(6, content)
+            }
+            SysmonSegment::Gpu => self.icon_text(
+                &cfg.gpu.icon,
+                self.segment_color(segment),
+                text(self.gpu_text()),
+            ),
+            SysmonSegment::Disk => self
+                .config
+                .flow(12, self.info.disks.iter().map(|disk| self.disk(disk))),
+            SysmonSegment::Net => match &self.info.net {
+                Some(net) => self.config.flow(
+                    12,
+                    [
+                        self.rate(&cfg.net.down_icon, net.down, self.segment_color(segment)),
+                        self.rate(&cfg.net.up_icon, net.up, self.segment_color(segment)),
+                    ],
+                ),
+                None => self.config.text_with_icon(&cfg.net.icon, "N/A"),
+            },
+        });
+
+        self.config.section(self.config.flow(12, segments)).into()
+    }
+
+    /// Top of the temperature graph in °C, as samples are kept in °C.
+    fn temp_graph_max(&self) -> f32 {
+        let cfg = &self.config.bar.sysmon.temp;
+        match cfg.graph_max.or(cfg.thresholds.critical) {
+            Some(max) => celsius(&self.config, max),
+            None => 100.,
+        }
+    }
+
+    fn gpu_text(&self) -> String {
+        let cfg = &self.config.bar.sysmon.gpu;
+        let Some(gpu) = self.info.gpu else {
+            return "N/A".to_string();
+        };
+        let mut parts = Vec::new();
+        if let Some(usage) = gpu.usage {
+            parts.push(format!("{usage:.*}%", cfg.precision));
+        }
+        if cfg.show_vram
+            && let Some((used, total)) = gpu.vram
+        {
+            parts.push(format!("{used:.1}/{total:.0} GB"));
+        }
+        if cfg.show_temp
+            && let Some(temp) = gpu.temp
+        {
+            parts.push(format!("{:.0}°", temperature(&self.config, temp)));
+        }
+        parts.join(" ")
+    }
+
+    fn disk(&self, disk: &DiskInfo) -> iced::Element<'_, Message> {
+        let cfg = &self.config.bar.sysmon.disk;
+        let mount = cfg.mounts.iter().find(|m| m.path == disk.mount_point);
+        let icon = mount.and_then(|m| m.icon.as_ref()).unwrap_or(&cfg.icon);
+        let color = self.level_color(&cfg.thresholds, disk_level(&self.config, disk));
+
+        let mut content = format!("{:.*}/{:.0} GB", cfg.precision, disk.used, disk.total);
+        if cfg.show_free {
+            let free = disk.total - disk.used;
+            content.push_str(&format!(" ({free:.*} free)", cfg.precision));
+        }
+        if cfg.show_io
+            && let Some((read, write)) = disk.io
+        {
+            content.push_str(&format!(
+                " R {} W {}",
+                format_rate(read, false),
+                format_rate(write, false)
+            ));
+        }
+        self.icon_text(icon, color, text(content))
+    }
+
+    fn rate(
+        &self,
+        icon: &Icon,
+        bytes_per_second: f32,
+        color: Option<Color>,
+    ) -> iced::Element<'_, Message> {
+        let fixed_width = self.config.bar.sysmon.net.fixed_width;
+        let rate = text(format_rate(bytes_per_second, fixed_width));
+        let rate = if fixed_width {
+            rate.font(iced::Font::MONOSPACE)
+        } else {
+            rate
+        };
+        self.icon_text(icon, color, rate)
+    }
+
+    /// An icon with its text, both in `color` if given, laid out like `text_with_icon`.
+    fn icon_text<'a>(
+        &self,
+        icon_cfg: &Icon,
+        color: Option<Color>,
+        content: Text<'a>,
+    ) -> iced::Element<'a, Message> {
+        let icon = icon(
+            &icon_cfg.name,
+            color.or_else(|| self.config.theme.resolve_color(&icon_cfg.color)),
+        );
+        let content = match color {
+            Some(color) => content.color(color),
+            None => content,
+        };
+        let spacing = if self.config.bar.position.is_vertical() {
+            2
+        } else {
+            6
+        };
+        self.config.flow(spacing, [icon.into(), content.into()])
+    }
+
+    fn segment_color(&self, segment: &SysmonSegment) -> Option<Color> {
+        let thresholds = thresholds(&self.config, segment)?;
+        self.level_color(thresholds, self.info.level(&self.config, segment))
+    }
+
+    /// The color of a level, or `None` to keep the icon's color.
+    fn level_color(&self, thresholds: &Thresholds, level: Level) -> Option<Color> {
+        let (color, blink) = match level {
+            Level::Normal => return None,
+            Level::Warning => (&thresholds.warning_color, false),
+            Level::Critical => (&thresholds.critical_color, thresholds.blink),
+        };
+        let color = self.config.theme.resolve_color(color)?;
+        Some(if blink {
+            color.scale_alpha(1. - BLINK_FADE * self.blink.get())
+        } else {
+            color
+        })
+    }
+
+    /// Graph of the recent samples of a metric, colored like its icon.
+    fn history_graph(
+        &self,
+        samples: &RingBuffer,
+        max: f32,
+        icon: &Icon,
+    ) -> iced::Element<'_, Message> {
+        Sparkline::new(samples.iter(), max)
+            .slots(samples.capacity())
+            .width(self.graph_width(GRAPH_WIDTH))
+            .height(GRAPH_HEIGHT)
+            .color(self.icon_color(icon))
+            .into()
+    }
+
+    fn per_core_graph(&self, icon: &Icon) -> iced::Element<'_, Message> {
+        let width = self.info.cpu_cores.len() as f32 * CORE_BAR_WIDTH;
+        Sparkline::new(self.info.cpu_cores.iter().copied(), 100.)
+            .width(self.graph_width(width))
+            .height(GRAPH_HEIGHT)
+            .color(self.icon_color(icon))
+            .into()
+    }
+
+    fn graph_width(&self, width: f32) -> f32 {
+        if self.config.bar.position.is_vertical() {
+            width.min(VERTICAL_GRAPH_WIDTH)
+        } else {
+            width
+        }
+    }
+
+    fn icon_color(&self, icon: &Icon) -> iced::Color {
+        self.config
+            .theme
+            .resolve_color(&icon.color)
+            .unwrap_or(iced::Color::WHITE)
+    }
+
+    pub fn subscription(config: &Config) -> iced::Subscription<Message> {
+        iced::advanced::subscription::from_recipe(SysmonSubscription {
+            probe_interval_ms: config.bar.sysmon.probe_interval_ms,
+            history_length: config.bar.sysmon.history_length,
+            temp: config
+                .bar
+                .sysmon
+                .segments
+                .contains(&SysmonSegment::Temp)
+                .then(|| {
+                    (
+                        config.bar.sysmon.temp.sensor.clone(),
+                        config.bar.sysmon.temp.aggregation,
+                    )
+                }),
+            gpu: config
+                .bar
+                .sysmon
+                .segments
+                .contains(&SysmonSegment::Gpu)
+                .then(|| {
+                    (
+                        config.bar.sysmon.gpu.card.clone(),
+                        config.bar.sysmon.gpu.source,
+                    )
+                }),
+            disks: config
+                .bar
+                .sysmon
+                .segments
+                .contains(&SysmonSegment::Disk)
+                .then(|| {
+                    let mounts = &config.bar.sysmon.disk.mounts;
+                    mounts.iter().map(|m| m.path.clone()).collect()
+                }),
+            net: config
+                .bar
+                .sysmon
+                .segments
+                .contains(&SysmonSegment::Net)
+                .then(|| config.bar.sysmon.net.interface.clone()),
+        })
+    }
+}
+
+#[derive(Hash)]
+struct SysmonSubscription {
+    probe_interval_ms: u64,
+    history_length: usize,
+    /// Sensor labels and their aggregation, if the temp segment is enabled.
+    temp: Option<(Vec<String>, TempAggregation)>,
+    /// GPU card and source, if the GPU segment is enabled.
+    gpu: Option<(Option<String>, GpuSource)>,
+    /// Mount points to probe, if the disk segment is enabled.
+    disks: Option<Vec<PathBuf>>,
+    /// Interface to probe, or `None` for the default route's, if the net segment is enabled.
+    net: Option<Option<String>>,
+}
+
+/// Probes of the optional segments, `None` while disabled.
+struct Probes {
+    temp: Option<TempProbe>,
+    gpu: Option<GpuProbe>,
+    disks: Option<DiskProbe>,
+    net: Option<NetProbe>,
+}
+
+impl iced::advanced::subscription::Recipe for SysmonSubscription {
+    type Output = Message;
+
+    fn hash(&self, state: &mut iced::advanced::subscription::Hasher) {
+        <Self as std::hash::Hash>::hash(self, state);
+    }
+
+    fn stream(
+        self: Box<Self>,
+        _input: iced::advanced::subscription::EventStream,
+    ) -> iced::runtime::futures::BoxStream<Self::Output> {
+        let prove_interval_ms = self.probe_interval_ms;
+        let history = History::new(self.history_length);
+        let probes = Probes {
+            temp: self
+                .temp
+                .map(|(sensors, aggregation)| TempProbe::new(&sensors, aggregation)),
+            gpu: self
+                .gpu
+                .map(|(card, source)| GpuProbe::new("/sys", card, source)),
+            disks: self
+                .disks
+                .map(|mount_points| DiskProbe::new(mount_points, "/proc/diskstats")),
+            net: self
+                .net
+                .map(|interface| NetProbe::new(interface, "/proc/net/route")),
+        };
+
+        let system = System::new_with_specifics(
+            RefreshKind::nothing()
+                .with_cpu(CpuRefreshKind::nothing().with_cpu_usage())
+                .with_memory(MemoryRefreshKind::nothing().with_ram()),
+        );
+
+        let stream = iced::futures::stream::unfold(
+            (system, probes, history, true),
+            move |(mut system, mut probes, mut history, first)| async move {
+                // during the first iteration, update immediately
+                if !first {
+                    tokio::time::sleep(Duration::from_millis(prove_interval_ms)).await;
+                }
+
+                system.refresh_cpu_usage();
+                system.refresh_memory();
+
+                let cpu_usage = system.global_cpu_usage();
+                let cpu_cores = system.cpus().iter().map(|cpu| cpu.cpu_usage()).collect();
+                let ram = ((system.total_memory() - system.available_memory()) as f64
+                    / 1_000_000_000.) as f32;
+                let ram_total = (system.total_memory() as f64 / 1_000_000_000.) as f32;
+
+                let cpu_temp = probes.temp.as_mut().and_then(TempProbe::read);
+
+                let gpu_info = match &mut probes.gpu {
+                    Some(gpu) => gpu.read().await,
+                    None => None,
+                };
+
+                let disk_infos = probes
+                    .disks
+                    .as_mut()
+                    .map(DiskProbe::read)
+                    .unwrap_or_default();
+                let net_info = probes.net.as_mut().and_then(NetProbe::read);
+
+                history.cpu_usage.push(cpu_usage);
+                // Without a reading, the graph keeps its last samples
+                if let Some(cpu_temp) = cpu_temp {
+                    history.cpu_temp.push(cpu_temp);
+                }
+                history.ram.push(ram);
+
+                let info = SysInfo {
+                    cpu_usage,
+                    cpu_cores,
+                    cpu_temp,
+                    ram,
+                    ram_total,
+                    gpu: gpu_info,
+                    disks: disk_infos,
+                    net: net_info,
+                    history: history.clone(),
+                };
+
+                Some((
+                    Message::SysinfoUpdate(info),
+                    (system, probes, history, false),
+                ))
+            },
+        );
+        stream.boxed()
+    }
+}
+
+/// Formats a throughput in bytes per second with an auto-scaled unit, e.g. `1.5 MB/s`. With
+/// `fixed_width`, the value is padded so that the text always has the same length.
+fn format_rate(bytes_per_second: f32, fixed_width: bool) -> String {
+    const UNITS: [&str; 4] = ["B/s", "KB/s", "MB/s", "GB/s"];
+    // Round to the shown precision before picking the unit, so that e.g. 999.96 B/s becomes
+    // `1.0 KB/s` rather than `1000.0 B/s`
+    let shown = |value: f32| {
+        if fixed_width {
+            (value * 10.).round() / 10.
+        } else {
+            value.round()
+        }
+    };
+    let mut value = bytes_per_second;
+    let mut unit = 0;
+    while shown(value) >= 1000. && unit < UNITS.len() - 1 {
+        value /= 1000.;
+        unit += 1;
+    }
+    if fixed_width {
+        format!("{value:>5.1} {:<4}", UNITS[unit])
+    } else if (value * 10.).round() < 100. && unit > 0 {
+        // Only show a decimal for single digits, to keep the text short
+        format!("{value:.1} {}", UNITS[unit])
+    } else {
+        format!("{value:.0} {}", UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn levels_follow_the_thresholds() {
+        let mut config = Config::default();
+        let sysmon = &mut config.bar.sysmon;
+        sysmon.cpu.thresholds.warning = Some(70.);
+        sysmon.cpu.thresholds.critical = Some(90.);
+        sysmon.net.thresholds.warning = Some(1e6);
+        sysmon.net.thresholds.critical = Some(10e6);
+
+        let info = |cpu_usage, down, up| SysInfo {
+            cpu_usage,
+            net: Some(NetInfo { down, up }),
+            ..Default::default()
+        };
+        let levels = |info: &SysInfo| {
+            [SysmonSegment::Cpu, SysmonSegment::Net].map(|segment| info.level(&config, &segment))
+        };
+        assert_eq!(levels(&info(10., 0., 0.)), [Level::Normal, Level::Normal]);
+        assert_eq!(
+            levels(&info(70., 2e6, 0.)),
+            [Level::Warning, Level::Warning]
+        );
+        // The faster direction counts
+        assert_eq!(
+            levels(&info(95., 0., 20e6)),
+            [Level::Critical, Level::Critical]
+        );
+        // No thresholds without a rate
+        let no_net = SysInfo {
+            net: None,
+            ..info(0., 0., 0.)
+        };
+        assert_eq!(no_net.level(&config, &SysmonSegment::Net), Level::Normal);
+    }
+
+    #[test]
+    fn net_crossing_into_critical_runs_its_command() {
+        let mut config = Config::default();
+        config.bar.sysmon.segments = vec![SysmonSegment::Net];
+        config.bar.sysmon.net.thresholds.critical = Some(10e6);
+        config.bar.sysmon.net.thresholds.critical_command = Some("notify-send net".to_string());
+
+        let info = |down| SysInfo {
+            net: Some(NetInfo { down, up: 0. }),
+            ..Default::default()
+        };
+        assert_eq!(
+            critical_commands(&config, &info(0.), &info(20e6)),
+            ["notify-send net"]
+        );
+        assert!(critical_commands(&config, &info(20e6), &info(30e6)).is_empty());
+    }
+
+    #[test]
+    fn temperature_thresholds_are_in_the_displayed_unit() {
+        let mut config = Config::default();
+        config.general.unit = Unit::Imperial;
+        config.bar.sysmon.temp.thresholds.critical = Some(176.);
+
+        let info = |cpu_temp| SysInfo {
+            cpu_temp,
+            ..Default::default()
+        };
+        let level = |info: SysInfo| info.level(&config, &SysmonSegment::Temp);
+        // 176 °F is 80 °C
+        assert_eq!(level(info(Some(79.))), Level::Normal);
+        assert_eq!(level(info(Some(80.))), Level::Critical);
+        // Without a sensor there's no reading, rather than 0 °C showing as 32 °F
+        assert_eq!(level(info(None)), Level::Normal);
+    }
+
+    #[test]
+    fn rates_are_rounded_before_picking_the_unit() {
+        assert_eq!(format_rate(999.94, true), "999.9 B/s ");
+        assert_eq!(format_rate(999.96, true), "  1.0 KB/s");
+        assert_eq!(format_rate(999_960., true), "  1.0 MB/s");
+        assert_eq!(format_rate(999.4, false), "999 B/s");
+        assert_eq!(format_rate(999.6, false), "1.0 KB/s");
+        assert_eq!(format_rate(9_960., false), "10 KB/s");
+        assert_eq!(format_rate(1_500_000., false), "1.5 MB/s");
+    }
+}
@@ -0,0 +1,157 @@
+//! Network throughput from `sysinfo::Networks`.
+
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use sysinfo::Networks;
+
+#[derive(Debug, Clone)]
+pub struct NetInfo {
+    /// Received and transmitted bytes per second.
+    pub down: f32,
+    pub up: f32,
+}
+
+pub struct NetProbe {
+    /// Interface to read, or the one of the default route.
+    interface: Option<String>,
+    networks: Networks,
+    /// Path of the IPv4 routing table, normally `/proc/net/route`.
+    routes: PathBuf,
+    /// When the previous probe was, `None` before the first one.
+    previous_time: Option<Instant>,
+}
+
+impl NetProbe {
+    pub fn new(interface: Option<String>, routes: impl Into<PathBuf>) -> Self {
+        Self {
+            interface,
+            networks: Networks::new_with_refreshed_list(),
+            routes: routes.into(),
+            previous_time: None,
+        }
+    }
+
+    /// Reads the rates since the previous probe, normally `probe_interval_ms` ago. The first
+    /// probe has nothing to compare with and returns `None`.
+    pub fn read(&mut self) -> Option<NetInfo> {
+        self.networks.refresh(true);
+        let now = Instant::now();
+        let elapsed = self
+            .previous_time
+            .replace(now)
+            .map(|previous| now.duration_since(previous).as_secs_f32())
+            .filter(|elapsed| *elapsed > 0.)?;
+
+        // The default route changes along with the connection, so look it up on every probe
+        let interface = match &self.interface {
+            Some(interface) => interface.clone(),
+            None => default_route_interface(&self.routes)?,
+        };
+        let data = self.networks.list().get(&interface)?;
+        let rate = |bytes: u64| bytes as f32 / elapsed;
+        Some(NetInfo {
+            down: rate(data.received()),
+            up: rate(data.transmitted()),
+        })
+    }
+}
+
+/// Interface of the default IPv4 route with the lowest metric.
+fn default_route_interface(routes: &Path) -> Option<String> {
+    let routes = std::fs::read_to_string(routes).ok()?;
+    routes
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            // Iface Destination Gateway Flags RefCnt Use Metric ...
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            let destination = *fields.get(1)?;
+            let metric = fields.get(6)?.parse::<u32>().ok()?;
+            (destination == "00000000").then(|| (metric, fields[0].to_string()))
+        })
+        .min()
+        .map(|(_, interface)| interface)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn routes(name: &str, routes: &[(&str, &str, u32)]) -> PathBuf {
+        let mut contents = String::from(
+            "Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT\n",
+        );
+        for (interface, destination, metric) in routes {
+            contents.push_str(&format!(
+                "{interface}\t{destination}\t0100A8C0\t0003\t0\t0\t{metric}\t00000000\t0\t0\t0\n"
+            ));
+        }
+        let path = std::env::temp_dir().join(format!("limbo-{name}-{}", std::process::id()));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    /// Some interface of this machine, usually `lo`.
+    fn any_interface() -> String {
+        let networks = Networks::new_with_refreshed_list();
+        networks
+            .list()
+            .keys()
+            .next()
+            .expect("no network interface")
+            .clone()
+    }
+
+    #[test]
+    fn picks_the_default_route_with_the_lowest_metric() {
+        let path = routes(
+            "routes",
+            &[
+                ("wlan0", "00000000", 600),
+                ("eth0", "0000A8C0", 10),
+                ("eth0", "00000000", 100),
+            ],
+        );
+        assert_eq!(default_route_interface(&path).as_deref(), Some("eth0"));
+        let _ = std::fs::remove_file(&path);
+
+        let path = routes("routes-none", &[("eth0", "0000A8C0", 100)]);
+        assert_eq!(default_route_interface(&path), None);
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(
+            default_route_interface(Path::new("/nonexistent/route")),
+            None
+        );
+    }
+
+    #[test]
+    fn the_first_probe_has_no_rates() {
+        let path = routes("routes-probe", &[(any_interface().as_str(), "00000000", 0)]);
+        let mut probe = NetProbe::new(None, &path);
+        assert!(probe.read().is_none());
+
+        std::thread::sleep(Duration::from_millis(10));
+        let info = probe
+            .read()
+            .expect("rates of the default route's interface");
+        assert!(info.down >= 0. && info.up >= 0.);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn no_rates_without_the_interface() {
+        let path = routes("routes-missing", &[("limbo-missing0", "00000000", 0)]);
+        let mut probe = NetProbe::new(None, &path);
+        assert!(probe.read().is_none());
+        assert!(probe.read().is_none());
+        let _ = std::fs::remove_file(&path);
+
+        let mut probe = NetProbe::new(Some("limbo-missing0".to_string()), "/nonexistent/route");
+        assert!(probe.read().is_none());
+        assert!(probe.read().is_none());
+    }
+}
@@ -0,0 +1,73 @@
+//! CPU temperature from the sensors matching the configured labels.
+
+use regex::Regex;
+use sysinfo::Components;
+
+use crate::config::types::TempAggregation;
+
+pub struct TempProbe {
+    components: Components,
+    sensors: Vec<Regex>,
+    aggregation: TempAggregation,
+    /// No sensor matched and that was reported, so don't report it on every probe.
+    warned: bool,
+}
+
+impl TempProbe {
+    pub fn new(sensors: &[String], aggregation: TempAggregation) -> Self {
+        Self {
+            components: Components::new_with_refreshed_list(),
+            sensors: sensors.iter().map(|sensor| glob(sensor)).collect(),
+            aggregation,
+            warned: false,
+        }
+    }
+
+    /// Reads the temperature in °C, combining all matching sensors.
+    pub fn read(&mut self) -> Option<f32> {
+        self.components.refresh(true);
+
+        let temperatures = self
+            .components
+            .list()
+            .iter()
+            .filter(|c| self.sensors.iter().any(|sensor| sensor.is_match(c.label())))
+            .filter_map(|c| c.temperature())
+            .collect::<Vec<_>>();
+        if temperatures.is_empty() {
+            if !self.warned {
+                let labels = self
+                    .components
+                    .list()
+                    .iter()
+                    .map(|c| format!("\"{}\"", c.label()))
+                    .collect::<Vec<_>>();
+                eprintln!(
+                    "CPU temperature not found, available sensors: {}",
+                    labels.join(", ")
+                );
+                self.warned = true;
+            }
+            return None;
+        }
+
+        Some(match self.aggregation {
+            TempAggregation::Max => temperatures.into_iter().fold(f32::MIN, f32::max),
+            TempAggregation::Avg => temperatures.iter().sum::<f32>() / temperatures.len() as f32,
+        })
+    }
+}
+
+/// Matches the whole label, with `*` standing for any text and `?` for any character.
+fn glob(pattern: &str) -> Regex {
+    let mut regex = String::from("^");
+    for c in pattern.chars() {
+        match c {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+    Regex::new(&regex).expect("escaped glob is a valid regex")
+}
@@ -0,0 +1,142 @@
+use std::rc::Rc;
+
+use iced::widget::text;
+use regex::Regex;
+
+use crate::GlobalState;
+use crate::components::system_icon;
+use crate::config::Config;
+use crate::desktop_environment::WindowInfo;
+use crate::message::Message;
+
+struct Rewrite {
+    app_id: Option<Regex>,
+    title: Regex,
+    replacement: String,
+}
+
+pub struct WindowTitle {
+    config: Rc<Config>,
+    rewrites: Vec<Rewrite>,
+    /// `None` until the desktop reports the focused window, which not every backend does.
+    window: Option<Option<WindowInfo>>,
+}
+
+impl WindowTitle {
+    pub fn new(global_state: &GlobalState) -> Self {
+        let config = global_state.config.clone();
+        Self {
+            rewrites: compile_rewrites(&config),
+            config,
+            window: global_state.focused_window.clone(),
+        }
+    }
+
+    pub fn update(&mut self, message: &Message) {
+        if let Message::FocusedWindowChanged(window) = message {
+            self.window = Some(window.clone());
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.window.is_none()
+    }
+
+    pub fn view(&self) -> iced::Element<'_, Message> {
+        let cfg = &self.config.bar.window_title;
+        let vertical = self.config.bar.position.is_vertical();
+
+        let Some(window) = self.window.as_ref().and_then(Option::as_ref) else {
+            if vertical {
+                return self
+                    .config
+                    .section(self.config.icon(&cfg.empty.icon))
+                    .into();
+            }
+            return self
+                .config
+                .section(
+                    self.config
+                        .text_with_icon(&cfg.empty.icon, cfg.empty.text.as_str()),
+                )
+                .into();
+        };
+
+        // App ids are often capitalized X11 classes, while icons are named in lowercase
+        let app_icon =
+            |app_id: &str| system_icon(app_id).or_else(|| system_icon(&app_id.to_lowercase()));
+        let icon = window
+            .app_id
+            .as_deref()
+            .filter(|_| cfg.app_icon)
+            .and_then(app_icon)
+            .unwrap_or_else(|| self.config.icon(&cfg.fallback_icon).into());
+
+        // There's no room for the title on vertical bars
+        if vertical {
+            return self.config.section(icon).into();
+        }
+
+        let title = truncate(&self.rewrite(window), cfg.max_length);
+        self.config
+            .section(self.config.flow(8, [icon, text(title).center().into()]))
+            .into()
+    }
+
+    fn rewrite(&self, window: &WindowInfo) -> String {
+        let mut title = window.title.clone();
+        for rewrite in &self.rewrites {
+            let app_matches = match (&rewrite.app_id, &window.app_id) {
+                (None, _) => true,
+                (Some(regex), Some(app_id)) => regex.is_match(app_id),
+                (Some(_), None) => false,
+            };
+            if app_matches {
+                title = rewrite
+                    .title
+                    .replace_all(&title, rewrite.replacement.as_str())
+                    .into_owned();
+            }
+        }
+        title
+    }
+}
+
+/// Compiles the configured rewrite rules, skipping invalid ones.
+fn compile_rewrites(config: &Config) -> Vec<Rewrite> {
+    config
+        .bar
+        .window_title
+        .rewrites
+        .iter()
+        .filter_map(|rewrite| {
+            let compile = |pattern: &str| {
+                Regex::new(pattern)
+                    .inspect_err(|e| eprintln!("Ignoring window title rewrite: {e}"))
+                    .ok()
+            };
+            Some(Rewrite {
+                app_id: match &rewrite.app_id {
+                    Some(app_id) => Some(compile(app_id)?),
+                    None => None,
+                },
+                title: compile(&rewrite.title)?,
+                replacement: rewrite.replacement.clone(),
+            })
+        })
+        .collect()
+}
+
+fn truncate(title: &str, max_length: usize) -> String {
+    if title.chars().count() <= max_length {
+        return title.to_string();
+    }
+    let mut truncated = title
+        .chars()
+        .take(max_length.saturating_sub(1))
+        .collect::<String>()
+        .trim_end()
+        .to_string();
+    truncated.push('…');
+    truncated
+}
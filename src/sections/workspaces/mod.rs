@@ -1,16 +1,17 @@
 use std::rc::Rc;
 
-use iced::Border;
 use iced::advanced::mouse;
-use iced::widget::{Row, container, mouse_area, text};
+use iced::widget::{container, image, mouse_area, text};
+use iced::{Alignment, Border};
 
 use crate::GlobalState;
 use crate::config::Config;
-use crate::desktop_environment::WorkspaceInfo;
+use crate::config::types::WorkspaceAction;
+use crate::desktop_environment::{WorkspaceId, WorkspaceInfo};
 use crate::message::Message;
 
 mod state;
-use state::WorkspaceState;
+use state::{ICON_SIZE, ICON_SPACING, Label, WorkspaceState};
 
 pub struct Workspaces {
     config: Rc<Config>,
@@ -48,56 +49,122 @@ impl Workspaces {
     }
 
     pub fn view(&self) -> iced::Element<'_, Message> {
+        let vertical = self.config.bar.position.is_vertical();
         let workspace_icons = self
             .states
             .iter()
             .map(|w| {
                 let color = w.color();
                 let width = w.width();
+                let outer = (w.slot() - width) / 2.;
+                // Labels need a thicker pill
+                let across = if matches!(w.label, Label::None) {
+                    5.
+                } else {
+                    2.
+                };
 
-                mouse_area(
-                    container(container(text("")).padding([5., width]).style(
-                        move |_: &iced::Theme| {
-                            container::Style {
-                                background: Some(color.into()),
-                                border: Border {
-                                    radius: 20.0.into(), // High radius for pill shape
-                                    ..Default::default()
-                                },
-                                ..Default::default()
-                            }
+                // The pill grows along the bar's main axis.
+                let pill = container(self.label(&w.label)).style(move |_: &iced::Theme| {
+                    container::Style {
+                        background: Some(color.into()),
+                        border: Border {
+                            radius: 20.0.into(), // High radius for pill shape
+                            ..Default::default()
                         },
-                    ))
-                    .padding([8., 15. - width]),
-                )
+                        ..Default::default()
+                    }
+                });
+                let pill = if vertical {
+                    pill.height(width)
+                        .align_y(Alignment::Center)
+                        .padding([0., across])
+                } else {
+                    pill.width(width)
+                        .align_x(Alignment::Center)
+                        .padding([across, 0.])
+                };
+
+                let cfg = &self.config.bar.workspaces;
+                let area = mouse_area(container(pill.clip(true)).padding(if vertical {
+                    [outer, 8.]
+                } else {
+                    [8., outer]
+                }))
                 .on_press(Message::FocusWorkspace(w.info.id))
-                .on_scroll(|delta| {
-                    let y = match delta {
+                .on_scroll(move |delta| {
+                    let delta = match delta {
                         mouse::ScrollDelta::Pixels { y, .. } => y,
-                        mouse::ScrollDelta::Lines { y, .. } => y,
+                        // Switch once per wheel notch
+                        mouse::ScrollDelta::Lines { y, .. } => y * cfg.scroll.threshold,
                     };
-                    if y == 0.0 {
+                    if delta == 0.0 {
                         Message::AnimationTick
                     } else {
-                        // TODO: debounce scrolling with `ScrollDelta::Pixels`
-                        Message::CycleWorkspace { forward: y <= 0.0 }
+                        Message::ScrollWorkspaces {
+                            output: self.output_name.clone(),
+                            delta,
+                        }
                     }
-                })
-                .into()
+                });
+                let area = match action_message(cfg.middle_click, w.info.id) {
+                    Some(message) => area.on_middle_press(message),
+                    None => area,
+                };
+                let area = match action_message(cfg.right_click, w.info.id) {
+                    Some(message) => area.on_right_press(message),
+                    None => area,
+                };
+                area.into()
             })
             .collect::<Vec<_>>();
 
         self.config
-            .section(Row::from_vec(workspace_icons))
-            .padding([0, 8])
+            .section(self.config.flow(0, workspace_icons))
+            .padding(if vertical { [8, 0] } else { [0, 8] })
             .into()
     }
 
+    fn label(&self, label: &Label) -> iced::Element<'_, Message> {
+        match label {
+            Label::None => text("").into(),
+            Label::Text(label) => text(label.clone())
+                .size(11)
+                .style(|theme: &iced::Theme| text::Style {
+                    color: Some(theme.palette().background),
+                })
+                .into(),
+            Label::Icons(icons) => self.config.flow(
+                ICON_SPACING,
+                icons.iter().map(|icon| {
+                    image(image::Handle::from_path(icon))
+                        .width(ICON_SIZE)
+                        .height(ICON_SIZE)
+                        .into()
+                }),
+            ),
+        }
+    }
+
+    /// Whether there are no workspaces to show on this output, e.g. without a compositor backend.
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+
     pub fn animation_running(&self) -> bool {
         self.states.iter().any(|w| w.animation_running())
     }
 }
 
+fn action_message(action: WorkspaceAction, id: WorkspaceId) -> Option<Message> {
+    match action {
+        WorkspaceAction::None => None,
+        WorkspaceAction::Focus => Some(Message::FocusWorkspace(id)),
+        WorkspaceAction::MoveWindow => Some(Message::MoveWindowToWorkspace(id)),
+        WorkspaceAction::ToggleOverview => Some(Message::ToggleOverview),
+    }
+}
+
 fn update_states(
     output_name: &String,
     workspace_infos: &[WorkspaceInfo],
@@ -107,6 +174,58 @@ fn update_states(
     workspace_infos
         .iter()
         .filter(|info| info.output.as_ref() == Some(output_name))
-        .map(|info| WorkspaceState::from_existing(old_states, info.clone(), config))
+        .enumerate()
+        .map(|(position, info)| {
+            WorkspaceState::from_existing(old_states, info.clone(), position, config)
+        })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use iced::futures::StreamExt;
+
+    use super::*;
+    use crate::desktop_environment::DesktopBackend;
+    use crate::desktop_environment::mock_desktop::MockDesktop;
+    use crate::desktop_environment::mock_desktop::tests::workspace;
+
+    #[tokio::test]
+    async fn states_follow_focus_calls() {
+        let config = Config::default();
+        let output = "DP-1".to_string();
+        let mut mock = MockDesktop::new(
+            vec![vec![
+                workspace("DP-1", 1, true, true),
+                workspace("DP-1", 2, false, false),
+                workspace("HDMI-A-1", 3, true, false),
+            ]],
+            Duration::ZERO,
+        );
+        let mut events = Box::pin(mock.events());
+
+        let states = update_states(&output, &events.next().await.unwrap(), &[], &config);
+        let ids = states.iter().map(|w| w.info.id).collect::<Vec<_>>();
+        assert_eq!(ids, [1, 2]);
+
+        mock.focus_workspace(2);
+        let workspace_infos = events.next().await.unwrap();
+        let mut states = update_states(&output, &workspace_infos, &states, &config);
+        let active = states
+            .iter()
+            .filter(|w| w.info.is_active)
+            .map(|w| w.info.id);
+        assert_eq!(active.collect::<Vec<_>>(), [2]);
+
+        // The focused pill grows while the other one shrinks
+        for _ in 0..60 {
+            for w in &mut states {
+                w.update();
+            }
+        }
+        assert!(states[1].width() > states[0].width());
+        assert_eq!(*mock.focus_calls().lock().unwrap(), [2]);
+    }
+}
@@ -1,19 +1,96 @@
+use std::path::PathBuf;
+
 use iced::Color;
 
-use crate::animation::{Eased, Easing};
+use crate::animation::{Eased, Easing, Lerpable};
+use crate::components::system_icon_path;
+use crate::config::Config;
+use crate::config::types::WorkspaceLabel;
 use crate::desktop_environment::WorkspaceInfo;
 
+/// Size of app icons in workspace pills.
+pub const ICON_SIZE: f32 = 14.;
+/// Space between app icons in workspace pills.
+pub const ICON_SPACING: f32 = 2.;
+/// Rough width of a label character, used to size pills before their text is laid out.
+const CHAR_WIDTH: f32 = 7.;
+/// Space around each pill along the bar.
+const PILL_GAP: f32 = 8.;
+
+#[derive(Debug, Clone)]
+pub enum Label {
+    None,
+    Text(String),
+    Icons(Vec<PathBuf>),
+}
+
+impl Label {
+    /// `position` is the index of the workspace among those on its output.
+    fn new(info: &WorkspaceInfo, position: usize, config: &Config) -> Self {
+        let index = || Label::Text((position + 1).to_string());
+        match config.bar.workspaces.label {
+            WorkspaceLabel::None => Label::None,
+            WorkspaceLabel::Index => index(),
+            WorkspaceLabel::Name => match &info.name {
+                Some(name) => Label::Text(name.clone()),
+                None => index(),
+            },
+            WorkspaceLabel::Icons => {
+                let mut app_ids = info.app_ids.clone();
+                app_ids.sort();
+                app_ids.dedup();
+                Label::Icons(
+                    app_ids
+                        .iter()
+                        .filter_map(|app_id| {
+                            system_icon_path(app_id)
+                                .or_else(|| system_icon_path(&app_id.to_lowercase()))
+                        })
+                        .take(config.bar.workspaces.max_icons)
+                        .collect(),
+                )
+            }
+        }
+    }
+
+    /// Estimated length of the label along the bar.
+    fn length(&self, vertical: bool) -> f32 {
+        match self {
+            Label::None => 0.,
+            Label::Text(_) if vertical => ICON_SIZE,
+            Label::Text(text) => text.chars().count() as f32 * CHAR_WIDTH,
+            Label::Icons(icons) if icons.is_empty() => 0.,
+            Label::Icons(icons) => icons.len() as f32 * (ICON_SIZE + ICON_SPACING) - ICON_SPACING,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct WorkspaceState {
     pub info: WorkspaceInfo,
+    pub label: Label,
+    /// Length of the pill along the bar.
     width: Eased<f32>,
+    width_targets: [f32; 2],
     color: Eased<Color>,
+    /// Urgent workspaces pulse between their color and `urgent_color`.
+    pulse: Eased<f32>,
+    urgent_color: Color,
+    /// Urgency was already seen by visiting the workspace.
+    acknowledged: bool,
 }
 
 impl WorkspaceState {
-    fn new(info: WorkspaceInfo, config: &crate::config::Config) -> Self {
+    fn new(info: WorkspaceInfo, label: Label, config: &Config) -> Self {
+        let width_targets = width_targets(&label, config);
         Self {
-            width: Eased::new(width_target_idx(&info), Easing::Linear, 100., &[5.0, 11.0]),
+            width: Eased::new(
+                width_target_idx(&info),
+                Easing::Linear,
+                100.,
+                &width_targets,
+            ),
+            width_targets,
             color: Eased::new(
                 color_target_idx(&info),
                 Easing::Smoothstep,
@@ -31,9 +108,17 @@ impl WorkspaceState {
                         .theme
                         .resolve_color(&config.bar.workspaces.color.active)
                         .unwrap_or(Color::from_rgb8(137, 180, 250)),
+                    config
+                        .theme
+                        .resolve_color(&config.bar.workspaces.color.special)
+                        .unwrap_or(Color::from_rgb8(203, 166, 247)),
                 ],
             ),
+            pulse: Eased::new(0, Easing::Smoothstep, 600., &[0., 1.]),
+            urgent_color: urgent_color(config),
+            acknowledged: info.is_urgent && info.is_active,
             info,
+            label,
         }
     }
 }
@@ -42,43 +127,93 @@ impl WorkspaceState {
     pub fn from_existing(
         states: &[WorkspaceState],
         info: WorkspaceInfo,
-        config: &crate::config::Config,
+        position: usize,
+        config: &Config,
     ) -> Self {
+        let label = Label::new(&info, position, config);
         if let Some(state) = states.iter().find(|s| s.info.id == info.id) {
+            let width_targets = width_targets(&label, config);
             Self {
-                width: state.width.clone().with_target_idx(width_target_idx(&info)),
+                width: state
+                    .width
+                    .clone()
+                    .with_targets(&width_targets)
+                    .with_target_idx(width_target_idx(&info)),
+                width_targets,
                 color: state.color.clone().with_target_idx(color_target_idx(&info)),
+                pulse: state.pulse.clone(),
+                urgent_color: state.urgent_color,
+                acknowledged: info.is_urgent && (info.is_active || state.acknowledged),
                 info,
+                label,
             }
         } else {
-            Self::new(info, config)
+            Self::new(info, label, config)
         }
     }
 
     pub fn animation_running(&self) -> bool {
-        self.width.is_running() || self.color.is_running()
+        self.width.is_running()
+            || self.color.is_running()
+            || self.is_urgent()
+            || self.pulse.is_running()
     }
 
     pub fn update(&mut self) {
         self.width.update();
         self.color.update();
+        self.pulse.update();
+        // Keep pulsing while urgent, and fade out once it isn't
+        if !self.pulse.is_running() {
+            if self.is_urgent() {
+                let target_idx = if self.pulse.get() > 0.5 { 0 } else { 1 };
+                self.pulse.set_target_idx(target_idx);
+            } else {
+                self.pulse.set_target_idx(0);
+            }
+        }
+    }
+
+    /// Whether a window on the workspace requested attention since it was last visited.
+    fn is_urgent(&self) -> bool {
+        self.info.is_urgent && !self.acknowledged
     }
 
     pub fn width(&self) -> f32 {
         self.width.get()
     }
 
+    /// Length of the space taken by the pill along the bar, which fits it when active.
+    pub fn slot(&self) -> f32 {
+        self.width_targets[1] + PILL_GAP
+    }
+
     pub fn color(&self) -> Color {
-        self.color.get()
+        Color::lerp(&self.color.get(), &self.urgent_color, self.pulse.get())
     }
 }
 
+fn urgent_color(config: &Config) -> Color {
+    config
+        .theme
+        .resolve_color(&config.bar.workspaces.color.urgent)
+        .unwrap_or(Color::from_rgb8(243, 139, 168))
+}
+
+/// Pill lengths when inactive and active.
+fn width_targets(label: &Label, config: &Config) -> [f32; 2] {
+    let length = label.length(config.bar.position.is_vertical());
+    [10. + length, 22. + length]
+}
+
 fn width_target_idx(info: &WorkspaceInfo) -> usize {
     if info.is_active { 1 } else { 0 }
 }
 
 fn color_target_idx(info: &WorkspaceInfo) -> usize {
-    if info.is_active {
+    if info.is_special {
+        3
+    } else if info.is_active {
         2
     } else if info.has_windows {
         1
@@ -0,0 +1,10 @@
+//! Commands from the config, which are run with `sh -c`.
+
+use tokio::process::Command;
+
+/// A `sh -c` process running `command`.
+pub fn command(command: &str) -> Command {
+    let mut process = Command::new("sh");
+    process.arg("-c").arg(command);
+    process
+}